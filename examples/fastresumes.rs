@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::{fs, io};
 use std::path::Path;
 
-use yabel::{Decoder, Item, BString, Bencode};
+use yabel::{Decoder, Item, BString, Bencode, Parser, Token};
 
 fn main() -> io::Result<()> {
     let source = "resumes"; // directory with `*.fastresume` files
@@ -27,8 +27,6 @@ fn replace_paths<P: AsRef<Path>, S: AsRef<str>>(source: P, target: P, old: S, ne
 
     fs::create_dir_all(&target)?;
 
-    let qbt_save_path = "qBt-savePath".into();
-
     for entry in fs::read_dir(source)? {
         let entry = entry?;
         let path = entry.path();
@@ -36,13 +34,12 @@ fn replace_paths<P: AsRef<Path>, S: AsRef<str>>(source: P, target: P, old: S, ne
         let v = fs::read(&path)?;
 
         let opt = Decoder::new(&v)
-            .decode()
+            .decode_single()
             .ok()
-            .and_then(|v| v.into_iter().next())
             .and_then(|i| i.dictionary());
 
         if let Some(mut bd) = opt {
-            if let Some(Item::String(BString(b))) = bd.0.get_mut(&qbt_save_path) {
+            if let Some(Item::String(BString(b))) = bd.get_mut("qBt-savePath") {
                 replace(b, old, new);
             }
 
@@ -56,23 +53,28 @@ fn replace_paths<P: AsRef<Path>, S: AsRef<str>>(source: P, target: P, old: S, ne
 }
 
 fn print_save_paths<P: AsRef<Path>>(source: P) -> io::Result<()> {
-    let qbt_save_path = "qBt-savePath".into();
-
     for entry in fs::read_dir(source)? {
         let entry = entry?;
         let path = entry.path();
 
         let v = fs::read(&path).unwrap();
 
-        let opt = Decoder::new(&v)
-            .decode()
-            .ok()
-            .and_then(|v| v.into_iter().next())
-            .and_then(|i| i.dictionary());
+        // There is no need to decode the whole file to read a single field.
+        let mut parser = Parser::new(&v);
+
+        if let Some(Ok(Token::DictStart)) = parser.next() {
+            while let Some(Ok(Token::Key(key))) = parser.next() {
+                if key == b"qBt-savePath" {
+                    if let Some(Ok(Token::Bytes(s))) = parser.next() {
+                        println!("qBt-savePath: {}", String::from_utf8_lossy(s));
+                    }
+
+                    break;
+                }
 
-        if let Some(bd) = opt {
-            if let Some(Item::String(s)) = bd.0.get(&qbt_save_path) {
-                println!("{}: {:?}", qbt_save_path, s);
+                if parser.skip_value().is_err() {
+                    break;
+                }
             }
         }
     }
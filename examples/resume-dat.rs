@@ -1,6 +1,6 @@
 use std::fs;
 
-use yabel::{BDictionary, Decoder, Settings};
+use yabel::{BDictionary, Bencode, Decoder, Settings};
 
 #[rustfmt::skip]
 fn main() {
@@ -17,12 +17,28 @@ fn main() {
     // not be preserved in the decoded result.
     let result = Decoder::new(&v)
         .setting(Settings::UnsortedDictionaries) // try to comment/uncomment this line
-        .decode()
+        .decode_single()
         .unwrap();
 
-    let BDictionary(d) = result.into_iter().next().unwrap().dictionary().unwrap();
+    let BDictionary(d) = result.dictionary().unwrap();
 
     for k in d.keys() {
         println!("{}", k);
     }
-}
+
+    // To keep the original order, decode dictionaries as `BOrderedDictionary`. Re-encoding
+    // such a dictionary produces exactly the same bytes, so the ".fileguard" checksum stays valid.
+    let result = Decoder::new(&v)
+        .setting(Settings::UnsortedDictionaries)
+        .setting(Settings::PreserveDictionaryOrder)
+        .decode_single()
+        .unwrap();
+
+    let d = result.ordered_dictionary().unwrap();
+
+    for k in d.keys() {
+        println!("{}", k);
+    }
+
+    assert_eq!(d.encode(), v);
+}
\ No newline at end of file
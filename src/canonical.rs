@@ -0,0 +1,169 @@
+use std::fmt;
+
+use crate::{DecodeError, Decoder, PathSegment, Settings};
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A kind of deviation from the canonical form.
+pub enum DeviationKind {
+    /// A dictionary key is smaller than the key before it.
+    UnsortedKeys,
+    /// A dictionary key occurs more than once.
+    DuplicateKey,
+    /// An integer or a string length has a `+` sign.
+    PlusSign,
+    /// There is whitespace between items.
+    Whitespace,
+    /// A string length has leading zeros.
+    NonMinimalLength,
+    /// An integer has leading zeros.
+    LeadingZeros,
+    /// An integer is a negative zero.
+    NegativeZero,
+}
+
+impl fmt::Display for DeviationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviationKind::UnsortedKeys => write!(f, "unsorted dictionary keys"),
+            DeviationKind::DuplicateKey => write!(f, "duplicate dictionary key"),
+            DeviationKind::PlusSign => write!(f, "plus sign"),
+            DeviationKind::Whitespace => write!(f, "whitespace"),
+            DeviationKind::NonMinimalLength => write!(f, "non-minimal string length"),
+            DeviationKind::LeadingZeros => write!(f, "leading zeros"),
+            DeviationKind::NegativeZero => write!(f, "negative zero"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A deviation from the canonical form found in the input.
+pub struct Deviation {
+    /// The kind of the deviation.
+    pub kind: DeviationKind,
+    /// The offset of the input byte at which the deviating item (or whitespace) starts.
+    pub offset: usize,
+    /// The path of dictionary keys and list indices leading to the deviating item.
+    pub path: Vec<PathSegment>,
+}
+
+/// Returns `true` if the input is one or more items in canonical form.
+///
+/// Canonical bencode has sorted dictionaries without duplicate keys, integers and string
+/// lengths without `+` signs or leading zeros, no negative zero, and no whitespace. Such
+/// input is encoded back exactly as it is.
+pub fn is_canonical(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && Decoder::new(bytes).decode().is_ok()
+}
+
+/// Rewrites the input into canonical form, see [`is_canonical`].
+///
+/// The input is decoded with [`Settings::Lenient`]: unsorted dictionaries, `+` signs, leading
+/// zeros, negative zero and whitespace between items are tolerated, and the last value of
+/// a duplicate key wins. Every deviation from the canonical form is reported.
+///
+/// ```
+/// use yabel::{canonicalize, DeviationKind};
+///
+/// let (bytes, deviations) = canonicalize(b"d 1:bi+1e 01:ai-0e e").unwrap();
+///
+/// assert_eq!(bytes, b"d1:ai0e1:bi1ee");
+///
+/// let kinds: Vec<_> = deviations.iter().map(|d| d.kind).collect();
+///
+/// assert_eq!(
+///     kinds,
+///     [
+///         DeviationKind::Whitespace,
+///         DeviationKind::PlusSign,
+///         DeviationKind::Whitespace,
+///         DeviationKind::NonMinimalLength,
+///         DeviationKind::UnsortedKeys,
+///         DeviationKind::NegativeZero,
+///         DeviationKind::Whitespace,
+///     ]
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the input is malformed beyond the tolerated deviations.
+pub fn canonicalize(bytes: &[u8]) -> Result<(Vec<u8>, Vec<Deviation>), DecodeError> {
+    let mut decoder = Decoder::new(bytes).setting(Settings::Lenient);
+    let items = decoder.decode()?;
+
+    let mut canonical = Vec::with_capacity(bytes.len());
+
+    for item in &items {
+        item.encode_to(&mut canonical).expect("writing into a vector does not fail");
+    }
+
+    Ok((canonical, decoder.deviations().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    fn kinds(input: &[u8]) -> (Vec<u8>, Vec<DeviationKind>) {
+        let (bytes, deviations) = canonicalize(input).unwrap();
+
+        (bytes, deviations.into_iter().map(|d| d.kind).collect())
+    }
+
+    #[test]
+    fn canonical_input() {
+        for input in [&b"i0e"[..], b"i-1e", b"0:", b"d1:ai1e1:bli2eee", b"i1e3:foo"] {
+            assert!(is_canonical(input), "input = {:?}", input);
+            assert_eq!(canonicalize(input).unwrap(), (input.to_vec(), vec![]));
+        }
+    }
+
+    #[test]
+    fn non_canonical_input() {
+        for input in [&b""[..], b"i+1e", b"i01e", b"i-0e", b"+1:a", b"01:a", b"d1:bi1e1:ai2ee", b"d1:ai1e1:ai2ee", b" i1e", b"li1e e"] {
+            assert!(!is_canonical(input), "input = {:?}", input);
+        }
+    }
+
+    #[test]
+    fn deviations() {
+        use DeviationKind::*;
+
+        assert_eq!(kinds(b"i+10e"), (b"i10e".to_vec(), vec![PlusSign]));
+        assert_eq!(kinds(b"i-007e"), (b"i-7e".to_vec(), vec![LeadingZeros]));
+        assert_eq!(kinds(b"i-000e"), (b"i0e".to_vec(), vec![NegativeZero]));
+        assert_eq!(kinds(b"+003:abc"), (b"3:abc".to_vec(), vec![PlusSign, NonMinimalLength]));
+        assert_eq!(kinds(b"d1:ai1e1:ai2ee"), (b"d1:ai2ee".to_vec(), vec![DuplicateKey]));
+        assert_eq!(kinds(b"l i1e\n\ti2e\r\n e "), (b"li1ei2ee".to_vec(), vec![Whitespace; 4]));
+    }
+
+    #[test]
+    fn deviation_context() {
+        let (_, deviations) = canonicalize(b"d3:food1:bi1e1:ai+2eee").unwrap();
+
+        assert_eq!(
+            deviations,
+            [
+                Deviation {
+                    kind: DeviationKind::UnsortedKeys,
+                    offset: 13,
+                    path: vec![PathSegment::from("foo")],
+                },
+                Deviation {
+                    kind: DeviationKind::PlusSign,
+                    offset: 16,
+                    path: vec![PathSegment::from("foo"), PathSegment::from("a")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(canonicalize(b"i++1e").map_err(|e| e.kind()), Err(ErrorKind::UnexpectedByte(b'+')));
+        assert_eq!(canonicalize(b"li1e").map_err(|e| e.kind()), Err(ErrorKind::UnexpectedEndOfBuffer));
+        assert_eq!(canonicalize(b"i-e").map_err(|e| e.kind()), Err(ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,565 @@
+use std::borrow::Cow;
+use std::{str, vec};
+
+use serde::de::{self, Deserialize, DeserializeSeed, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::items::{BBigInteger, BString, Item};
+use crate::{DecodeError, Decoder, PathSegment};
+
+/// Deserializes a value from bencode.
+///
+/// The input is decoded with the default [`Decoder`] settings and must contain exactly one
+/// item, see [`Decoder::decode_single`]. Strings and byte slices can be borrowed from the
+/// input. Type mismatches are reported as [`ErrorKind::Custom`](crate::ErrorKind::Custom)
+/// errors along with the path to the failing item.
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Ping<'a> {
+///     t: &'a [u8],
+///     q: &'a str,
+/// }
+///
+/// let ping: Ping = yabel::from_bytes(b"d1:q4:ping1:t2:aa1:y1:qe").unwrap();
+///
+/// assert_eq!((ping.t, ping.q), (&b"aa"[..], "ping"));
+/// ```
+pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, DecodeError> {
+    from_item(Decoder::new(bytes).decode_single()?)
+}
+
+/// Deserializes a value from an item.
+///
+/// Strings are borrowed if the item borrows them, see [`from_bytes`] for details.
+pub fn from_item<'de, T: Deserialize<'de>>(item: Item<'de>) -> Result<T, DecodeError> {
+    T::deserialize(ItemDeserializer(item))
+}
+
+impl de::Error for DecodeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        DecodeError::custom(msg.to_string())
+    }
+}
+
+fn unexpected<'i>(item: &'i Item<'_>) -> Unexpected<'i> {
+    match item {
+        Item::String(s) => match str::from_utf8(&s.0) {
+            Ok(s) => Unexpected::Str(s),
+            Err(_) => Unexpected::Bytes(&s.0),
+        },
+        Item::Integer(i) => Unexpected::Signed(i.0),
+        Item::BigInteger(_) => Unexpected::Other("big integer"),
+        Item::List(_) => Unexpected::Seq,
+        Item::Dictionary(_) | Item::OrderedDictionary(_) => Unexpected::Map,
+    }
+}
+
+/// Visits a string as `str` if it is valid UTF-8, or as bytes otherwise.
+fn visit_string<'de, V: Visitor<'de>>(s: BString<'de>, visitor: V) -> Result<V::Value, DecodeError> {
+    match s.0 {
+        Cow::Borrowed(b) => match str::from_utf8(b) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => visitor.visit_borrowed_bytes(b),
+        },
+        Cow::Owned(b) => match String::from_utf8(b) {
+            Ok(s) => visitor.visit_string(s),
+            Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+        },
+    }
+}
+
+/// Visits a big integer as the first of `u64`, `i128` or `u128` it fits into.
+fn visit_big_integer<'de, V: Visitor<'de>>(i: &BBigInteger, visitor: V) -> Result<V::Value, DecodeError> {
+    if let Some(u) = i.to_u64() {
+        visitor.visit_u64(u)
+    } else if let Some(i) = i.to_i128() {
+        visitor.visit_i128(i)
+    } else if let Some(u) = i.to_u128() {
+        visitor.visit_u128(u)
+    } else {
+        Err(de::Error::invalid_type(Unexpected::Other("big integer"), &visitor))
+    }
+}
+
+/// Deserializes a value from an item, consuming it.
+struct ItemDeserializer<'de>(Item<'de>);
+
+impl<'de> de::Deserializer<'de> for ItemDeserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Item::String(s) => visit_string(s, visitor),
+            Item::Integer(i) => visitor.visit_i64(i.0),
+            Item::BigInteger(i) => visit_big_integer(&i, visitor),
+            Item::List(l) => visitor.visit_seq(SeqAccess::new(l.0)),
+            Item::Dictionary(d) => visitor.visit_map(MapAccess::new(d.0.into_iter().collect())),
+            Item::OrderedDictionary(d) => visitor.visit_map(MapAccess::new(d.0)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Item::Integer(i) if i.0 == 0 || i.0 == 1 => visitor.visit_bool(i.0 == 1),
+            item => Err(de::Error::invalid_type(unexpected(&item), &visitor)),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Item::String(s) => match s.0 {
+                Cow::Borrowed(b) => match str::from_utf8(b) {
+                    Ok(s) => visitor.visit_borrowed_str(s),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Bytes(b), &visitor)),
+                },
+                Cow::Owned(b) => match String::from_utf8(b) {
+                    Ok(s) => visitor.visit_string(s),
+                    Err(e) => Err(de::Error::invalid_value(Unexpected::Bytes(e.as_bytes()), &visitor)),
+                },
+            },
+            item => Err(de::Error::invalid_type(unexpected(&item), &visitor)),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Item::String(BString(Cow::Borrowed(b))) => visitor.visit_borrowed_bytes(b),
+            Item::String(BString(Cow::Owned(b))) => visitor.visit_byte_buf(b),
+            item => Err(de::Error::invalid_type(unexpected(&item), &visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    /// `()` has no encoding of its own and is omitted when serialized, so no item
+    /// deserializes into it.
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::invalid_type(unexpected(&self.0), &visitor))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Byte strings can be deserialized as sequences of bytes as well, e.g. into `Vec<u8>`.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Item::String(s) => {
+                let mut bytes = de::value::SeqDeserializer::<_, DecodeError>::new(s.0.iter().copied());
+                let value = visitor.visit_seq(&mut bytes)?;
+
+                bytes.end()?;
+
+                Ok(value)
+            },
+            item => ItemDeserializer(item).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut entries: Vec<_> = match self.0 {
+            Item::String(s) => return visitor.visit_enum(EnumAccess(s, None)),
+            Item::Dictionary(d) => d.0.into_iter().collect(),
+            Item::OrderedDictionary(d) => d.0,
+            item => return Err(de::Error::invalid_type(unexpected(&item), &visitor)),
+        };
+
+        match entries.len() {
+            1 => {
+                let (key, value) = entries.pop().expect("the dictionary has one entry");
+
+                visitor.visit_enum(EnumAccess(key, Some(value)))
+            },
+            n => Err(de::Error::invalid_length(n, &"a dictionary with a single key")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char map struct identifier ignored_any
+    }
+}
+
+/// Deserializes list elements.
+struct SeqAccess<'de> {
+    items: vec::IntoIter<Item<'de>>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> {
+    fn new(items: Vec<Item<'de>>) -> Self {
+        Self {
+            items: items.into_iter(),
+            index: 0,
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        let Some(item) = self.items.next() else {
+            return Ok(None);
+        };
+
+        let index = self.index;
+
+        self.index += 1;
+
+        seed.deserialize(ItemDeserializer(item))
+            .map(Some)
+            .map_err(|e| e.within(PathSegment::Index(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// Deserializes dictionary entries.
+struct MapAccess<'de> {
+    entries: vec::IntoIter<(BString<'de>, Item<'de>)>,
+    value: Option<(Vec<u8>, Item<'de>)>,
+}
+
+impl<'de> MapAccess<'de> {
+    fn new(entries: Vec<(BString<'de>, Item<'de>)>) -> Self {
+        Self {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some((key.0.to_vec(), value));
+
+        seed.deserialize(KeyDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let (key, value) = self.value.take().expect("next_value is called after next_key");
+
+        seed.deserialize(ItemDeserializer(value))
+            .map_err(|e| e.within(PathSegment::Key(key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// Deserializes dictionary keys and enum variant names.
+///
+/// Keys are always strings, but can be deserialized into integers as well.
+struct KeyDeserializer<'de>(BString<'de>);
+
+impl<'de> KeyDeserializer<'de> {
+    fn parse_integer<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        let s = str::from_utf8(&self.0 .0).ok();
+
+        match (s.and_then(|s| s.parse().ok()), s.and_then(BBigInteger::new)) {
+            (Some(i), _) => visitor.visit_i64(i),
+            (None, Some(i)) => visit_big_integer(&i, visitor),
+            (None, None) => Err(de::Error::invalid_type(unexpected(&Item::String(self.0)), &visitor)),
+        }
+    }
+}
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.parse_integer(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visit_string(self.0, visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ItemDeserializer(Item::String(self.0)).deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        ItemDeserializer(Item::String(self.0)).deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    deserialize_integer_key! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char option unit unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes an enum variant, either a bare name or a single entry dictionary.
+struct EnumAccess<'de>(BString<'de>, Option<Item<'de>>);
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = DecodeError;
+    type Variant = VariantAccess<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let key = self.0 .0.to_vec();
+        let variant = seed.deserialize(KeyDeserializer(self.0))?;
+
+        Ok((variant, VariantAccess(key, self.1)))
+    }
+}
+
+struct VariantAccess<'de>(Vec<u8>, Option<Item<'de>>);
+
+impl<'de> VariantAccess<'de> {
+    fn value(self, expected: &dyn de::Expected) -> Result<(ItemDeserializer<'de>, Vec<u8>), DecodeError> {
+        match self.1 {
+            Some(item) => Ok((ItemDeserializer(item), self.0)),
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, expected)),
+        }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'de> {
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.1 {
+            None => Ok(()),
+            Some(item) => Err(de::Error::invalid_type(unexpected(&item), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+        let (value, key) = self.value(&"newtype variant")?;
+
+        seed.deserialize(value).map_err(|e| e.within(PathSegment::Key(key)))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        let (value, key) = self.value(&visitor)?;
+
+        de::Deserializer::deserialize_seq(value, visitor).map_err(|e| e.within(PathSegment::Key(key)))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (value, key) = self.value(&visitor)?;
+
+        de::Deserializer::deserialize_map(value, visitor).map_err(|e| e.within(PathSegment::Key(key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
+
+    use crate::{bencode, from_bytes, from_item, to_bytes, Decoder, ErrorKind, PathSegment, Settings};
+
+    #[derive(Debug, Deserialize, PartialEq, serde::Serialize)]
+    struct File<'a> {
+        length: u64,
+        #[serde(borrow, default, with = "serde_bytes", skip_serializing_if = "Option::is_none")]
+        md5sum: Option<&'a [u8]>,
+        path: Vec<&'a str>,
+        #[serde(default)]
+        private: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, serde::Serialize)]
+    enum Message {
+        Ping,
+        Get(u32),
+        Pair(i8, i8),
+        Piece { index: u32 },
+    }
+
+    #[test]
+    fn borrowed_fields() {
+        let input = b"d6:lengthi42e6:md5sum2:\x00\xff4:pathl1:a1:be7:privatei1ee";
+
+        let file: File = from_bytes(input).unwrap();
+
+        assert_eq!(
+            file,
+            File {
+                length: 42,
+                md5sum: Some(b"\x00\xff"),
+                path: vec!["a", "b"],
+                private: true,
+            }
+        );
+        assert_eq!(to_bytes(&file).unwrap(), input);
+
+        let file: File = from_bytes(b"d6:lengthi1e4:pathlee").unwrap();
+
+        assert_eq!((file.md5sum, file.private), (None, false));
+    }
+
+    #[test]
+    fn owned_items() {
+        let item = Decoder::new(b"d6:lengthi1e4:pathl3:fooee").decode_single().unwrap().into_owned();
+
+        #[derive(Deserialize)]
+        struct Owned<'a> {
+            #[serde(borrow)]
+            path: Vec<Cow<'a, str>>,
+            length: i64,
+        }
+
+        let file: Owned = from_item(item).unwrap();
+
+        assert_eq!((file.path, file.length), (vec![Cow::from("foo")], 1));
+    }
+
+    #[test]
+    fn enums() {
+        let messages = vec![Message::Ping, Message::Get(1), Message::Pair(-1, 2), Message::Piece { index: 3 }];
+
+        let bytes = to_bytes(&messages).unwrap();
+
+        assert_eq!(from_bytes::<Vec<Message>>(&bytes).unwrap(), messages);
+        assert!(from_bytes::<Message>(b"d3:Geti1e4:Pingi0ee").is_err());
+    }
+
+    #[test]
+    fn maps_and_bytes() {
+        let map: HashMap<u32, Vec<u8>> = from_bytes(b"d1:13:abc2:104:\xff\x00\x01\x02e").unwrap();
+
+        assert_eq!(map[&1], b"abc");
+        assert_eq!(map[&10], b"\xff\x00\x01\x02");
+
+        let ordered = Decoder::new(b"d1:b1:x1:a1:ye")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        let map: BTreeMap<String, String> = from_item(ordered).unwrap();
+
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn errors() {
+        let e = from_bytes::<File>(b"d6:lengthi1e4:pathl1:ai2eee").unwrap_err();
+
+        assert_eq!(e.kind(), ErrorKind::Custom);
+        assert_eq!(e.path(), &[PathSegment::from("path"), PathSegment::Index(1)]);
+        assert_eq!(e.to_string(), "invalid type: integer `2`, expected a borrowed string in `path[1]`");
+
+        let e = from_bytes::<File>(b"d4:pathlee").unwrap_err();
+
+        assert_eq!(e.message(), Some("missing field `length`"));
+
+        // decode errors are reported as is
+        let e = from_bytes::<File>(b"d4:pathle6:lengthi1ee").unwrap_err();
+
+        assert_eq!(e.kind(), ErrorKind::UnsortedDictionary);
+
+        let e = from_bytes::<()>(b"i1e").unwrap_err();
+
+        assert_eq!(e.to_string(), "invalid type: integer `1`, expected unit");
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(from_bytes::<u64>(b"i18446744073709551615e").unwrap(), u64::MAX);
+        assert_eq!(from_bytes::<i128>(b"i-9223372036854775809e").unwrap(), i128::from(i64::MIN) - 1);
+        assert_eq!(from_bytes::<u128>(b"i340282366920938463463374607431768211455e").unwrap(), u128::MAX);
+
+        let map: BTreeMap<u64, u8> = from_bytes(b"d20:18446744073709551615i1ee").unwrap();
+
+        assert_eq!(map[&u64::MAX], 1);
+
+        let e = from_bytes::<u128>(b"i340282366920938463463374607431768211456e").unwrap_err();
+
+        assert_eq!(e.to_string(), "invalid type: big integer, expected u128");
+
+        assert!(from_bytes::<i64>(b"i9223372036854775808e").is_err());
+    }
+
+    #[test]
+    fn items() {
+        let item = bencode!({ "length": 7, "path": ["x"] });
+
+        let file: File = from_item(item).unwrap();
+
+        assert_eq!(file.path, vec!["x"]);
+    }
+}
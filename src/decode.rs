@@ -1,34 +1,195 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
 use std::str;
 
 use crate::items::*;
-use crate::DecodeError;
+use crate::reader::Reader;
+use crate::util::MAX_DEPTH;
 use crate::ErrorKind::*;
+use crate::{DecodeError, Deviation, DeviationKind, ErrorKind, PathSegment};
 
 #[non_exhaustive]
 #[derive(Debug)]
 /// Decoder settings.
+///
+/// Only the nesting depth is limited by default, to 256 levels, so that deeply nested input
+/// can not overflow the stack. When decoding untrusted input, consider setting the other
+/// limits as well.
 pub enum Settings {
     /// Allow only sorted dictionaries.
     SortedDictionaries,
     /// Allow sorted and unsorted dictionaries.
     UnsortedDictionaries,
+    /// Decode dictionaries as [`BOrderedDictionary`], keeping their keys in the original order.
+    ///
+    /// Combined with [`Settings::UnsortedDictionaries`], this makes byte-for-byte round trips
+    /// of unsorted dictionaries possible.
+    PreserveDictionaryOrder,
+    /// Sets how duplicate keys of unsorted dictionaries are handled.
+    ///
+    /// Sorted dictionaries never allow duplicate keys. The default policy is
+    /// [`DuplicateKeyPolicy::Reject`].
+    DuplicateKeys(DuplicateKeyPolicy),
+    /// Limits the nesting depth of lists and dictionaries, 256 by default.
+    ///
+    /// A top level list or dictionary has a depth of 1. Raising the limit far above the
+    /// default risks a stack overflow.
+    MaxDepth(usize),
+    /// Limits the declared length of a single string.
+    MaxStringLength(usize),
+    /// Limits the number of decoded items.
+    ///
+    /// Every string (including dictionary keys), integer, list and dictionary counts as one item.
+    MaxItems(usize),
+    /// Limits the number of input bytes.
+    ///
+    /// [`StreamDecoder`](crate::StreamDecoder) applies this limit, as well as the item limit,
+    /// to every top level item separately.
+    MaxBytes(usize),
+    /// Accept integers and string lengths with leading zeros, as well as negative zero.
+    AllowLeadingZeros,
+    /// Accept `+` signs in integers and string lengths.
+    AllowPlusSigns,
+    /// Accept ASCII whitespace between items.
+    AllowWhitespace,
+    /// Accept all the quirks above, unsorted dictionaries and duplicate keys, the last value
+    /// of a duplicate key wins.
+    ///
+    /// This is meant for files written by old or broken clients. The tolerated deviations are
+    /// available through [`Decoder::deviations`], see also [`canonicalize`](crate::canonicalize).
+    Lenient,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// Duplicate dictionary key policy.
+///
+/// Used with [`Settings::DuplicateKeys`].
+pub enum DuplicateKeyPolicy {
+    /// Fail with [`ErrorKind::DuplicateKey`](crate::ErrorKind::DuplicateKey).
+    #[default]
+    Reject,
+    /// Keep the value of the first occurrence of a key.
+    KeepFirst,
+    /// Keep the value of the last occurrence of a key, at the position of the first one.
+    KeepLast,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Decoder limits, `None` means unlimited.
+pub(crate) struct Limits {
+    pub(crate) depth: Option<usize>,
+    pub(crate) string_length: Option<usize>,
+    pub(crate) items: Option<usize>,
+    pub(crate) bytes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            depth: Some(MAX_DEPTH),
+            string_length: None,
+            items: None,
+            bytes: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+/// Non-canonical encodings the decoder tolerates, `false` means rejected.
+pub(crate) struct Leniency {
+    /// `+` signs in integers and string lengths.
+    pub(crate) plus_sign: bool,
+    /// Leading zeros and negative zero in integers and string lengths.
+    pub(crate) leading_zeros: bool,
+    /// ASCII whitespace between items.
+    pub(crate) whitespace: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+/// Decoder options, shared by all decoders.
+pub(crate) struct Options {
+    pub(crate) allow_unsorted_dictionaries: bool,
+    pub(crate) preserve_dictionary_order: bool,
+    pub(crate) duplicate_keys: DuplicateKeyPolicy,
+    pub(crate) limits: Limits,
+    pub(crate) leniency: Leniency,
+}
+
+impl Options {
+    /// Applies a setting.
+    pub(crate) fn apply(&mut self, setting: Settings) {
+        match setting {
+            Settings::SortedDictionaries => self.allow_unsorted_dictionaries = false,
+            Settings::UnsortedDictionaries => self.allow_unsorted_dictionaries = true,
+            Settings::PreserveDictionaryOrder => self.preserve_dictionary_order = true,
+            Settings::DuplicateKeys(policy) => self.duplicate_keys = policy,
+            Settings::MaxDepth(n) => self.limits.depth = Some(n),
+            Settings::MaxStringLength(n) => self.limits.string_length = Some(n),
+            Settings::MaxItems(n) => self.limits.items = Some(n),
+            Settings::MaxBytes(n) => self.limits.bytes = Some(n),
+            Settings::AllowLeadingZeros => self.leniency.leading_zeros = true,
+            Settings::AllowPlusSigns => self.leniency.plus_sign = true,
+            Settings::AllowWhitespace => self.leniency.whitespace = true,
+            Settings::Lenient => {
+                self.allow_unsorted_dictionaries = true;
+                self.duplicate_keys = DuplicateKeyPolicy::KeepLast;
+                self.leniency = Leniency {
+                    plus_sign: true,
+                    leading_zeros: true,
+                    whitespace: true,
+                };
+            },
+        }
+    }
+}
+
+/// Returns `true` if `value` exceeds the `limit`.
+pub(crate) fn exceeds(value: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| value > limit)
+}
+
+/// A step of the path to the item being decoded.
+#[derive(Clone, Copy, Debug)]
+enum Step<'a> {
+    Key(&'a [u8]),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A value together with the raw bytes it was decoded from.
+pub struct Spanned<'a, T> {
+    /// The value.
+    pub value: T,
+    /// The position of the raw bytes in the input.
+    pub span: Range<usize>,
+    /// The raw bytes, exactly as they appear in the input.
+    pub bytes: &'a [u8],
 }
 
 /// Bencode decoder.
 pub struct Decoder<'a> {
-    bytes: &'a [u8],
-    cursor: usize,
-    allow_unsorted_dictionaries: bool,
+    reader: Reader<'a>,
+    path: Vec<Step<'a>>,
+    capture_keys: Vec<Vec<u8>>,
+    captures: Vec<Spanned<'a, Vec<PathSegment>>>,
+    deviations: Vec<Deviation>,
 }
 
 impl<'a> Decoder<'a> {
     /// Constructs a new `Decoder` with specified byte buffer.
     pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_options(bytes, Options::default())
+    }
+
+    /// Constructs a new `Decoder` with specified byte buffer and options.
+    pub(crate) fn with_options(bytes: &'a [u8], options: Options) -> Self {
         Self {
-            bytes,
-            cursor: 0,
-            allow_unsorted_dictionaries: false,
+            reader: Reader::new(bytes, options),
+            path: vec![],
+            capture_keys: vec![],
+            captures: vec![],
+            deviations: vec![],
         }
     }
 
@@ -38,174 +199,465 @@ impl<'a> Decoder<'a> {
     pub fn setting(self, setting: Settings) -> Self {
         let mut s = self;
 
-        match setting {
-            Settings::SortedDictionaries => s.allow_unsorted_dictionaries = false,
-            Settings::UnsortedDictionaries => s.allow_unsorted_dictionaries = true,
-        }
+        s.reader.options.apply(setting);
 
         s
     }
 
+    /// Records the raw bytes of every dictionary value stored under `key`.
+    ///
+    /// This is useful when the exact original bytes of an item are needed, e.g. to compute
+    /// the info-hash of a torrent. The recorded spans are available through
+    /// [`Decoder::captures`] after decoding.
+    pub fn capture(self, key: &[u8]) -> Self {
+        let mut s = self;
+
+        s.capture_keys.push(key.to_vec());
+
+        s
+    }
+
+    /// Returns the spans recorded for the keys chosen with [`Decoder::capture`], in the order
+    /// they were decoded.
+    ///
+    /// The value of each span is the path to the captured item, the last segment of the path
+    /// being the key itself.
+    pub fn captures(&self) -> &[Spanned<'a, Vec<PathSegment>>] {
+        &self.captures
+    }
+
+    /// Returns the deviations from the canonical form tolerated so far, in the order they were
+    /// found.
+    ///
+    /// Deviations are only tolerated with lenient settings, such as [`Settings::Lenient`].
+    ///
+    /// ```
+    /// use yabel::{Decoder, DeviationKind, Settings};
+    ///
+    /// let mut decoder = Decoder::new(b"d4:sizei007e4:namei-0ee").setting(Settings::Lenient);
+    /// let item = decoder.decode_single().unwrap();
+    ///
+    /// assert_eq!(item.get_int("size"), Some(7));
+    /// assert_eq!(decoder.deviations()[0].kind, DeviationKind::LeadingZeros);
+    /// assert_eq!(decoder.deviations().len(), 3);
+    /// ```
+    pub fn deviations(&self) -> &[Deviation] {
+        &self.deviations
+    }
+
+    /// Decodes items, together with the raw bytes of every top level item.
+    pub fn decode_spanned(&mut self) -> Result<Vec<Spanned<'a, Item<'a>>>, DecodeError> {
+        let mut items = vec![];
+
+        self.path.clear();
+        self.check_byte_limit()?;
+
+        while let Some(byte) = self.skip_whitespace() {
+            let start = self.reader.cursor;
+
+            let value = self.decode_item(byte)?;
+
+            items.push(Spanned {
+                value,
+                span: start..self.reader.cursor,
+                bytes: &self.reader.bytes[start..self.reader.cursor],
+            });
+        }
+
+        Ok(items)
+    }
+
     /// Decodes items.
     pub fn decode(&mut self) -> Result<Vec<Item<'a>>, DecodeError> {
         let mut items = vec![];
 
-        while let Some(byte) = self.bytes.get(self.cursor) {
+        self.path.clear();
+        self.check_byte_limit()?;
+
+        while let Some(byte) = self.skip_whitespace() {
             items.push(self.decode_item(byte)?);
         }
 
         Ok(items)
     }
 
+    /// Decodes a single item and returns it together with the offset right past its end.
+    ///
+    /// Any bytes after the item are left untouched, so calling this method again decodes
+    /// the next item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnexpectedEndOfBuffer`] if there are no more items.
+    pub fn decode_prefix(&mut self) -> Result<(Item<'a>, usize), DecodeError> {
+        match self.decode_next()? {
+            Some(item) => Ok((item, self.reader.cursor)),
+            None => Err(self.error(UnexpectedEndOfBuffer, self.reader.cursor, &[])),
+        }
+    }
+
+    /// Decodes exactly one item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TrailingData`] if there are any bytes after the item.
+    pub fn decode_single(&mut self) -> Result<Item<'a>, DecodeError> {
+        let (item, _) = self.decode_prefix()?;
+
+        self.skip_whitespace();
+
+        let end = self.reader.cursor;
+
+        if end < self.reader.bytes.len() {
+            return Err(self.error(TrailingData, end, &self.reader.bytes[end..]));
+        }
+
+        Ok(item)
+    }
+
+    /// Decodes the next item, if there is any.
+    pub(crate) fn decode_next(&mut self) -> Result<Option<Item<'a>>, DecodeError> {
+        self.path.clear();
+        self.check_byte_limit()?;
+
+        match self.skip_whitespace() {
+            Some(byte) => self.decode_item(byte).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Records a tolerated deviation at `offset`.
+    fn deviation(&mut self, kind: DeviationKind, offset: usize) {
+        let path = self.current_path();
+
+        self.deviations.push(Deviation { kind, offset, path });
+    }
+
+    /// Skips whitespace if it is tolerated, and returns the byte at the cursor position.
+    fn skip_whitespace(&mut self) -> Option<&'a u8> {
+        let bytes = self.reader.bytes;
+
+        if self.reader.options.leniency.whitespace {
+            let start = self.reader.cursor;
+
+            while bytes.get(self.reader.cursor).is_some_and(u8::is_ascii_whitespace) {
+                self.reader.cursor += 1;
+            }
+
+            if self.reader.cursor > start {
+                self.deviation(DeviationKind::Whitespace, start);
+            }
+        }
+
+        bytes.get(self.reader.cursor)
+    }
+
+    /// Constructs an error at `offset`, with the current path and the offending bytes.
+    fn error(&self, kind: ErrorKind, offset: usize, bytes: &[u8]) -> DecodeError {
+        DecodeError::new(kind, offset).with_path(self.current_path()).with_bytes(bytes)
+    }
+
+    /// Returns the path to the item being decoded.
+    fn current_path(&self) -> Vec<PathSegment> {
+        self.path
+            .iter()
+            .map(|step| match step {
+                Step::Key(k) => PathSegment::Key(k.to_vec()),
+                Step::Index(i) => PathSegment::Index(*i),
+            })
+            .collect()
+    }
+
+    /// Checks the input length against the byte limit.
+    fn check_byte_limit(&self) -> Result<(), DecodeError> {
+        match self.reader.options.limits.bytes {
+            Some(limit) if self.reader.bytes.len() > limit => Err(self.error(ByteLimitExceeded, limit, &[])),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts an item starting at the cursor position against the item limit.
+    fn count_item(&mut self) -> Result<(), DecodeError> {
+        self.reader.count_item().map_err(|e| e.with_path(self.current_path()))
+    }
+
+    /// Checks the depth of a list or a dictionary starting at the cursor position.
+    fn check_depth(&self) -> Result<(), DecodeError> {
+        if exceeds(self.path.len() + 1, self.reader.options.limits.depth) {
+            let cursor = self.reader.cursor;
+
+            return Err(self.error(DepthLimitExceeded, cursor, &self.reader.bytes[cursor..=cursor]));
+        }
+
+        Ok(())
+    }
+
     /// Decodes a single `Item`.
     ///
     /// # Error
     ///
     /// See [`DecodeError`] and [`ErrorKind`](crate::ErrorKind) for more details.
     fn decode_item(&mut self, byte: &u8) -> Result<Item<'a>, DecodeError> {
+        self.count_item()?;
+
         match byte {
             b'0'..=b'9' => Ok(Item::String(self.decode_string()?)),
-            b'i' => Ok(Item::Integer(self.decode_integer()?)),
+            b'+' if self.reader.options.leniency.plus_sign => Ok(Item::String(self.decode_string()?)),
+            b'i' => self.decode_integer(),
             b'l' => Ok(Item::List(self.decode_list()?)),
-            b'd' => Ok(Item::Dictionary(self.decode_dictionary()?)),
-            b => {
-                Err(DecodeError {
-                    kind: UnexpectedByte(*b),
-                })
+            b'd' => {
+                let pairs = self.decode_dictionary()?;
+
+                if self.reader.options.preserve_dictionary_order {
+                    Ok(Item::OrderedDictionary(BOrderedDictionary(pairs)))
+                } else {
+                    Ok(Item::Dictionary(BDictionary(pairs.into_iter().collect())))
+                }
             },
+            b => Err(self.error(UnexpectedByte(*b), self.reader.cursor, &[*b])),
         }
     }
 
-    /// Reads bytes from the buffer until `stop_byte` is reached and returns the read bytes.
-    ///
-    /// # Errors
-    ///
-    /// Returns [`UnexpectedEndOfBuffer`] if `stop_byte` was not reached.
-    fn read_bytes(&mut self, stop_byte: u8) -> Result<&[u8], DecodeError> {
-        self.bytes
-            .iter()
-            .skip(self.cursor)
-            .position(|b| b == &stop_byte)
-            .ok_or(DecodeError {
-                kind: UnexpectedEndOfBuffer,
-            })
-            .map(|pos| {
-                let pos = pos + self.cursor;
+    /// Reads a string and returns its bytes.
+    fn read_string(&mut self) -> Result<&'a [u8], DecodeError> {
+        let start = self.reader.cursor;
 
-                let bytes = &self.bytes[self.cursor..pos];
+        let mut deviations = vec![];
 
-                self.cursor = pos + 1;
+        let s = self
+            .reader
+            .read_string(|kind| deviations.push(kind))
+            .map_err(|e| e.with_path(self.current_path()))?;
 
-                bytes
-            })
+        for kind in deviations {
+            self.deviation(kind, start);
+        }
+
+        Ok(s)
     }
 
     /// Decodes a string.
     fn decode_string(&mut self) -> Result<BString<'a>, DecodeError> {
-        self.read_bytes(b':')
-            .and_then(parse_i64)
-            .map(|length| length as usize)
-            .and_then(|length| {
-                let s = self
-                    .bytes
-                    .get(self.cursor..self.cursor + length)
-                    .ok_or(DecodeError {
-                        kind: UnexpectedEndOfBuffer,
-                    })
-                    .map(|s| BString(Cow::from(s)));
-
-                self.cursor += length;
-
-                s
-            })
+        self.read_string().map(|s| BString(Cow::from(s)))
     }
 
-    /// Decodes an integer.
-    fn decode_integer(&mut self) -> Result<BInteger, DecodeError> {
-        self.cursor += 1;
+    /// Decodes an integer, falling back to [`BBigInteger`] if it does not fit into `i64`.
+    fn decode_integer(&mut self) -> Result<Item<'a>, DecodeError> {
+        let start = self.reader.cursor;
+
+        let mut deviations = vec![];
+
+        let (i, _) = self
+            .reader
+            .read_integer(|kind| deviations.push(kind))
+            .map_err(|e| e.with_path(self.current_path()))?;
+
+        for kind in deviations {
+            self.deviation(kind, start);
+        }
 
-        self.read_bytes(b'e').and_then(parse_i64).map(BInteger)
+        Ok(i)
     }
 
     /// Decodes a list.
     fn decode_list(&mut self) -> Result<BList<'a>, DecodeError> {
-        self.cursor += 1;
+        self.check_depth()?;
 
-        let mut items = vec![];
+        self.reader.cursor += 1;
 
-        let mut decode_is_done = false;
+        let mut items = vec![];
 
-        while let Some(byte) = self.bytes.get(self.cursor) {
+        while let Some(byte) = self.skip_whitespace() {
             if *byte == b'e' {
-                decode_is_done = true;
-                break;
+                self.reader.cursor += 1;
+
+                return Ok(BList(items));
             };
-            items.push(self.decode_item(byte)?);
-        }
 
-        self.cursor += 1;
+            self.path.push(Step::Index(items.len()));
 
-        if decode_is_done {
-            Ok(BList(items))
-        } else {
-            Err(DecodeError {
-                kind: UnexpectedEndOfBuffer,
-            })
+            items.push(self.decode_item(byte)?);
+
+            self.path.pop();
         }
+
+        Err(self.error(UnexpectedEndOfBuffer, self.reader.bytes.len(), &[]))
     }
 
-    /// Decodes a dictionary.
-    fn decode_dictionary(&mut self) -> Result<BDictionary<'a>, DecodeError> {
-        self.cursor += 1;
+    /// Decodes a dictionary and returns its key-value pairs in the original order.
+    fn decode_dictionary(&mut self) -> Result<Vec<(BString<'a>, Item<'a>)>, DecodeError> {
+        self.check_depth()?;
 
-        let mut items = vec![];
+        self.reader.cursor += 1;
+
+        let mut items: Vec<(BString, Item)> = vec![];
 
-        let mut decode_is_done = false;
+        // Positions of already decoded keys, used to find duplicates in unsorted dictionaries.
+        let mut positions: BTreeMap<&[u8], usize> = BTreeMap::new();
+        let mut previous: Option<&[u8]> = None;
 
-        while let Some(byte) = self.bytes.get(self.cursor).cloned() {
+        while let Some(byte) = self.skip_whitespace().cloned() {
             if byte == b'e' {
-                decode_is_done = true;
-                break;
+                self.reader.cursor += 1;
+
+                return Ok(items);
             };
 
-            let key = self.decode_item(&byte)?.string().ok_or(DecodeError {
-                kind: InvalidDictionaryKey,
-            })?;
+            let start = self.reader.cursor;
+
+            if !(byte.is_ascii_digit() || byte == b'+' && self.reader.options.leniency.plus_sign) {
+                return Err(self.error(InvalidDictionaryKey, start, &[byte]));
+            }
+
+            self.count_item()?;
+
+            let key = self.read_string()?;
+
+            let duplicate = if self.reader.options.allow_unsorted_dictionaries {
+                if previous.is_some_and(|previous| previous > key) {
+                    self.deviation(DeviationKind::UnsortedKeys, start);
+                }
 
-            if !self.allow_unsorted_dictionaries && items.last().map_or(false, |(k, _)| k > &key) {
-                return Err(DecodeError {
-                    kind: UnsortedDictionary,
+                positions.get(key).copied()
+            } else {
+                match items.last() {
+                    Some((k, _)) if &k.0[..] > key => return Err(self.error(UnsortedDictionary, start, key)),
+                    Some((k, _)) if &k.0[..] == key => return Err(self.error(DuplicateKey, start, key)),
+                    _ => None,
+                }
+            };
+
+            if duplicate.is_some() {
+                if self.reader.options.duplicate_keys == DuplicateKeyPolicy::Reject {
+                    return Err(self.error(DuplicateKey, start, key));
+                }
+
+                self.deviation(DeviationKind::DuplicateKey, start);
+            }
+
+            previous = Some(key);
+
+            let byte = self
+                .skip_whitespace()
+                .ok_or_else(|| self.error(UnexpectedEndOfBuffer, self.reader.cursor, &[]))?;
+
+            self.path.push(Step::Key(key));
+
+            let value_start = self.reader.cursor;
+
+            let value = self.decode_item(byte)?;
+
+            if self.capture_keys.iter().any(|k| k[..] == *key) {
+                self.captures.push(Spanned {
+                    value: self.current_path(),
+                    span: value_start..self.reader.cursor,
+                    bytes: &self.reader.bytes[value_start..self.reader.cursor],
                 });
             }
 
-            let byte = self.bytes.get(self.cursor).ok_or(DecodeError {
-                kind: UnexpectedEndOfBuffer,
-            })?;
+            self.path.pop();
+
+            match duplicate {
+                Some(i) if self.reader.options.duplicate_keys == DuplicateKeyPolicy::KeepLast => items[i].1 = value,
+                Some(_) => {},
+                None => {
+                    if self.reader.options.allow_unsorted_dictionaries {
+                        positions.insert(key, items.len());
+                    }
 
-            items.push((key, self.decode_item(byte)?));
+                    items.push((BString(Cow::from(key)), value));
+                },
+            }
         }
 
-        self.cursor += 1;
+        Err(self.error(UnexpectedEndOfBuffer, self.reader.bytes.len(), &[]))
+    }
+}
 
-        if decode_is_done {
-            Ok(BDictionary(items.into_iter().collect()))
-        } else {
-            Err(DecodeError {
-                kind: UnexpectedEndOfBuffer,
-            })
-        }
+/// The maximum number of bytes in a string length, enough for any `u64`.
+pub(crate) const MAX_LENGTH_DIGITS: usize = 20;
+
+/// Returns `true` if the byte may be a part of an integer or a string length.
+///
+/// Signs are only checked when the whole number is parsed, so that a stream can be rejected
+/// as soon as a byte that can never be valid arrives.
+pub(crate) fn is_number_byte(byte: u8) -> bool {
+    byte.is_ascii_digit() || byte == b'-' || byte == b'+'
+}
+
+/// Parses a string length from byte slice, tolerating the deviations allowed by `leniency`.
+///
+/// Every tolerated deviation is passed to `deviation`.
+pub(crate) fn parse_length_lenient(
+    bytes: &[u8],
+    leniency: Leniency,
+    mut deviation: impl FnMut(DeviationKind),
+) -> Result<usize, ErrorKind> {
+    parse_integer_lenient(bytes, leniency, |kind| match kind {
+        DeviationKind::LeadingZeros | DeviationKind::NegativeZero => deviation(DeviationKind::NonMinimalLength),
+        kind => deviation(kind),
+    })
+    .and_then(|length| length.as_int().and_then(|l| usize::try_from(l).ok()).ok_or(InvalidData))
+}
+
+/// Parses an integer from byte slice, tolerating the deviations allowed by `leniency`.
+///
+/// Returns [`Item::Integer`] if the integer fits into `i64`, or [`Item::BigInteger`]
+/// otherwise. Every tolerated deviation is passed to `deviation`.
+pub(crate) fn parse_integer_lenient(
+    bytes: &[u8],
+    leniency: Leniency,
+    mut deviation: impl FnMut(DeviationKind),
+) -> Result<Item<'static>, ErrorKind> {
+    let digits = match bytes {
+        [b'+', rest @ ..] if leniency.plus_sign && !rest.starts_with(b"+") && !rest.starts_with(b"-") => {
+            deviation(DeviationKind::PlusSign);
+            rest
+        },
+        _ => bytes,
+    };
+
+    let tolerated = match parse_i64(digits) {
+        Ok(i) => return Ok(Item::Integer(BInteger(i))),
+        Err(LeadingZeros) if leniency.leading_zeros => Some(DeviationKind::LeadingZeros),
+        Err(NegativeZero) if leniency.leading_zeros => Some(DeviationKind::NegativeZero),
+        Err(InvalidData) => None,
+        Err(kind) => return Err(kind),
+    };
+
+    let (sign, magnitude) = match digits {
+        [b'-', rest @ ..] => ("-", rest),
+        _ => ("", digits),
+    };
+
+    if magnitude.is_empty() || !magnitude.iter().all(u8::is_ascii_digit) {
+        return Err(InvalidData);
     }
+
+    if let Some(kind) = tolerated {
+        deviation(kind);
+    }
+
+    let magnitude = match magnitude.iter().position(|b| b != &b'0') {
+        Some(pos) => str::from_utf8(&magnitude[pos..]).map_err(|_e| InvalidData)?,
+        None => return Ok(Item::Integer(BInteger(0))),
+    };
+
+    BBigInteger::new(&format!("{}{}", sign, magnitude))
+        .map(Item::from)
+        .ok_or(InvalidData)
 }
 
 /// Parses an integer from byte slice.
-fn parse_i64(bytes: &[u8]) -> Result<i64, DecodeError> {
+pub(crate) fn parse_i64(bytes: &[u8]) -> Result<i64, ErrorKind> {
     match bytes[..] {
-        [b'-', b'0', _, ..] | [b'0', _, ..] => Err(DecodeError { kind: LeadingZeros }),
-        [b'-', b'0', ..] => Err(DecodeError { kind: NegativeZero }),
+        [b'+', ..] => Err(UnexpectedByte(b'+')),
+        [b'-', ref zeros @ ..] if !zeros.is_empty() && zeros.iter().all(|b| *b == b'0') => Err(NegativeZero),
+        [b'-', b'0', ..] | [b'0', _, ..] => Err(LeadingZeros),
         _ => {
             str::from_utf8(bytes)
-                .map_err(|_e| DecodeError { kind: InvalidData })
-                .and_then(|s| s.parse().map_err(|_e| DecodeError { kind: InvalidData }))
+                .map_err(|_e| InvalidData)
+                .and_then(|s| s.parse().map_err(|_e| InvalidData))
         },
     }
 }
@@ -216,7 +668,7 @@ mod tests {
 
     use crate::items::*;
     use crate::ErrorKind::*;
-    use crate::{DecodeError, Decoder, Settings};
+    use crate::{Bencode, Decoder, DuplicateKeyPolicy, PathSegment, Settings};
 
     fn process_string(expected: &str) {
         let input = format!("{}:{}", expected.len(), &expected);
@@ -241,7 +693,7 @@ mod tests {
 
         expected
             .into_iter()
-            .zip(actual.0.into_iter())
+            .zip(actual.0)
             .for_each(|(expected, actual)| assert_eq!(expected, actual))
     }
 
@@ -260,7 +712,7 @@ mod tests {
 
         expected_pairs
             .into_iter()
-            .zip(actual_pairs.into_iter())
+            .zip(actual_pairs)
             .for_each(|((k1, v1), (k2, v2))| {
                 assert_eq!(k1, k2);
                 assert_eq!(v1, v2);
@@ -286,12 +738,7 @@ mod tests {
     fn string_with_incorrect_length() {
         let input = b"7:foo";
 
-        assert_eq!(
-            Decoder::new(&input[..]).decode(),
-            Err(DecodeError {
-                kind: UnexpectedEndOfBuffer
-            })
-        );
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(UnexpectedEndOfBuffer));
     }
 
     #[test]
@@ -303,9 +750,11 @@ mod tests {
 
     #[test]
     fn negative_zero() {
-        let input = b"i-0e";
+        for input in [&b"i-0e"[..], b"i-000e"] {
+            assert_eq!(Decoder::new(input).decode().map_err(|e| e.kind()), Err(NegativeZero));
+        }
 
-        assert_eq!(Decoder::new(&input[..]).decode(), Err(DecodeError { kind: NegativeZero }));
+        assert_eq!(Decoder::new(b"i-007e").decode().map_err(|e| e.kind()), Err(LeadingZeros));
     }
 
     #[test]
@@ -320,32 +769,70 @@ mod tests {
         assert_eq!(expected, actual.0);
     }
 
+    #[test]
+    fn big_integer() {
+        for input in [&b"i18446744073709551616e"[..], b"i-9223372036854775809e", b"li123456789012345678901234567890ee"] {
+            let item = Decoder::new(input).decode_single().unwrap();
+
+            assert_eq!(item.encode(), input);
+        }
+
+        let item = Decoder::new(b"i9223372036854775808e").decode_single().unwrap();
+
+        assert_eq!(item.as_int(), None);
+        assert_eq!(item.as_u64(), Some(1 << 63));
+        assert_eq!(item.as_big_int().map(|i| i.as_str()), Some("9223372036854775808"));
+
+        let item = Decoder::new(b"i-9223372036854775808e").decode_single().unwrap();
+
+        assert_eq!(item, Item::Integer(BInteger(i64::MIN)));
+
+        for (input, kind) in [(&b"i0123456789012345678901e"[..], LeadingZeros), (b"i1234567890123456789x1e", InvalidData)] {
+            assert_eq!(Decoder::new(input).decode().map_err(|e| e.kind()), Err(kind));
+        }
+
+        // string lengths still have to fit into `usize`
+        assert_eq!(Decoder::new(b"123456789012345678901:a").decode().map_err(|e| e.kind()), Err(InvalidData));
+
+        let mut decoder = Decoder::new(b"i+00012345678901234567890e").setting(Settings::Lenient);
+
+        assert_eq!(decoder.decode_single().unwrap().encode(), b"i12345678901234567890e");
+        assert_eq!(decoder.deviations().len(), 2);
+    }
+
     #[test]
     fn minus() {
         let input = b"i-e";
 
-        assert_eq!(Decoder::new(&input[..]).decode(), Err(DecodeError { kind: InvalidData }));
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(InvalidData));
+    }
+
+    #[test]
+    fn plus_sign() {
+        for input in [&b"i+1e"[..], b"+1:a", b"d+1:ai1ee"] {
+            assert!(Decoder::new(input).decode().is_err(), "input = {:?}", input);
+        }
     }
 
     #[test]
     fn empty_integer() {
         let input = b"ie";
 
-        assert_eq!(Decoder::new(&input[..]).decode(), Err(DecodeError { kind: InvalidData }));
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(InvalidData));
     }
 
     #[test]
     fn integer_with_leading_zeros() {
         let input = b"i001e";
 
-        assert_eq!(Decoder::new(&input[..]).decode(), Err(DecodeError { kind: LeadingZeros }));
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(LeadingZeros));
     }
 
     #[test]
     fn malformed_integer() {
         let input = b"i-4AF54e";
 
-        assert_eq!(Decoder::new(&input[..]).decode(), Err(DecodeError { kind: InvalidData }));
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(InvalidData));
     }
 
     #[test]
@@ -365,7 +852,7 @@ mod tests {
 
     #[test]
     fn malformed_lists() {
-        let input = vec!["l4e", "l0:", "l3:gge", "li00002ee"];
+        let input = ["l4e", "l0:", "l3:gge", "li00002ee"];
 
         input.iter().for_each(|s| assert!(Decoder::new(s.as_bytes()).decode().is_err()))
     }
@@ -393,11 +880,245 @@ mod tests {
     #[test]
     fn unsorted_dictionary_without_settings() {
         let res = Decoder::new("d2:ccle2:bblee".as_bytes()).decode();
+        assert_eq!(res.map_err(|e| e.kind()), Err(UnsortedDictionary));
+    }
+
+    #[test]
+    fn ordered_dictionary() {
+        let input = b"d10:.fileguard3:foo2:#ad2:cc0:2:bb0:ee";
+
+        let item = Decoder::new(&input[..])
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode()
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+
+        let d = item.clone().ordered_dictionary().unwrap();
+        let keys: Vec<_> = d.keys().map(|k| &k.0[..]).collect();
+
+        assert_eq!(keys, [&b".fileguard"[..], b"#a"]);
+        assert_eq!(item.encode(), input);
+    }
+
+    #[test]
+    fn duplicate_keys_in_sorted_dictionary() {
+        let e = Decoder::new(b"d3:bari1e3:bari2ee").decode().unwrap_err();
+
+        assert_eq!(e.kind(), DuplicateKey);
+        assert_eq!(e.offset(), 9);
+        assert_eq!(e.bytes(), b"bar");
+
+        let res = Decoder::new(b"d3:bari1e3:bari2ee")
+            .setting(Settings::DuplicateKeys(DuplicateKeyPolicy::KeepLast))
+            .decode();
+        assert_eq!(res.map_err(|e| e.kind()), Err(DuplicateKey));
+    }
+
+    #[test]
+    fn lenient() {
+        use crate::DeviationKind;
+
+        let input = b"d8:announce3:url4:infod6:lengthi0010e04:name3:fooe7:privatei-0ee";
+
+        assert_eq!(Decoder::new(&input[..]).decode().map_err(|e| e.kind()), Err(LeadingZeros));
+
+        let mut decoder = Decoder::new(&input[..]).setting(Settings::Lenient);
+        let item = decoder.decode_single().unwrap();
+
+        assert_eq!(item.get_int("info.length"), Some(10));
+        assert_eq!(item.get_str("info.name"), Some("foo"));
+        assert_eq!(item.get_int("private"), Some(0));
+
+        let deviations: Vec<_> = decoder.deviations().iter().map(|d| (d.kind, d.offset)).collect();
+
         assert_eq!(
-            res,
-            Err(DecodeError {
-                kind: UnsortedDictionary
-            })
+            deviations,
+            [
+                (DeviationKind::LeadingZeros, 31),
+                (DeviationKind::NonMinimalLength, 37),
+                (DeviationKind::NegativeZero, 59)
+            ]
+        );
+        assert_eq!(decoder.deviations()[0].path, [PathSegment::from("info"), PathSegment::from("length")]);
+    }
+
+    #[test]
+    fn fine_grained_leniency() {
+        let inputs: [(&[u8], Settings); 4] = [
+            (b"i01e", Settings::AllowLeadingZeros),
+            (b"02:ab", Settings::AllowLeadingZeros),
+            (b"i+1e", Settings::AllowPlusSigns),
+            (b" i1e ", Settings::AllowWhitespace),
+        ];
+
+        for (input, setting) in inputs {
+            assert!(Decoder::new(input).decode().is_err(), "input = {:?}", input);
+            assert!(Decoder::new(input).setting(setting).decode_single().is_ok(), "input = {:?}", input);
+        }
+
+        let e = Decoder::new(b"i+01e").setting(Settings::AllowPlusSigns).decode().unwrap_err();
+
+        assert_eq!(e.kind(), LeadingZeros);
+    }
+
+    #[test]
+    fn duplicate_keys_in_unsorted_dictionary() {
+        let input = b"d4:infoi1e3:fooi0e4:infoi2ee";
+
+        let decode = |policy| {
+            Decoder::new(&input[..])
+                .setting(Settings::UnsortedDictionaries)
+                .setting(Settings::DuplicateKeys(policy))
+                .decode()
+                .map(|v| v.into_iter().next().unwrap().dictionary().unwrap().0.remove(&b"info"[..]).unwrap())
+                .map_err(|e| e.kind())
+        };
+
+        assert_eq!(decode(DuplicateKeyPolicy::Reject), Err(DuplicateKey));
+        assert_eq!(decode(DuplicateKeyPolicy::KeepFirst), Ok(1.into()));
+        assert_eq!(decode(DuplicateKeyPolicy::KeepLast), Ok(2.into()));
+    }
+
+    #[test]
+    fn spans() {
+        let input = b"i1eld3:fooi2eee";
+
+        let items = Decoder::new(&input[..]).decode_spanned().unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].bytes, b"i1e");
+        assert_eq!(items[1].span, 3..15);
+        assert_eq!(items[1].bytes, b"ld3:fooi2eee");
+        assert_eq!(items[1].value, vec![vec![("foo".into(), 2.into())].into_iter().collect()].into());
+    }
+
+    #[test]
+    fn captures() {
+        let input = b"d4:infod4:name1:a5:piecei1ee1:xd4:infoli1eeee";
+
+        let mut decoder = Decoder::new(&input[..]).capture(b"info");
+
+        decoder.decode().unwrap();
+
+        let captures = decoder.captures();
+
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures[0].value, [PathSegment::Key(b"info".to_vec())]);
+        assert_eq!(captures[0].bytes, b"d4:name1:a5:piecei1ee");
+        assert_eq!(captures[1].value, [PathSegment::Key(b"x".to_vec()), PathSegment::Key(b"info".to_vec())]);
+        assert_eq!(captures[1].span, 38..43);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let input = "l".repeat(100_000);
+
+        let e = Decoder::new(input.as_bytes()).setting(Settings::MaxDepth(64)).decode().unwrap_err();
+
+        assert_eq!(e.kind(), DepthLimitExceeded);
+        assert_eq!(e.offset(), 64);
+
+        assert!(Decoder::new(b"lllleeee").setting(Settings::MaxDepth(4)).decode().is_ok());
+
+        // the depth is limited by default
+        let e = Decoder::new(input.as_bytes()).decode().unwrap_err();
+
+        assert_eq!((e.kind(), e.offset()), (DepthLimitExceeded, 256));
+
+        let input = format!("{}{}", "l".repeat(300), "e".repeat(300));
+
+        assert!(Decoder::new(input.as_bytes()).setting(Settings::MaxDepth(300)).decode().is_ok());
+    }
+
+    #[test]
+    fn string_length_limit() {
+        let res = Decoder::new(b"999999999999:foo").setting(Settings::MaxStringLength(1024)).decode();
+        assert_eq!(res.map_err(|e| e.kind()), Err(StringLengthLimitExceeded));
+
+        assert!(Decoder::new(b"3:foo").setting(Settings::MaxStringLength(3)).decode().is_ok());
+    }
+
+    #[test]
+    fn item_limit() {
+        let res = Decoder::new(b"d3:fooi1ee").setting(Settings::MaxItems(2)).decode();
+        assert_eq!(res.map_err(|e| e.kind()), Err(ItemLimitExceeded));
+
+        assert!(Decoder::new(b"d3:fooi1ee").setting(Settings::MaxItems(3)).decode().is_ok());
+    }
+
+    #[test]
+    fn byte_limit() {
+        let res = Decoder::new(b"i1ei2e").setting(Settings::MaxBytes(5)).decode();
+        assert_eq!(res.map_err(|e| e.kind()), Err(ByteLimitExceeded));
+
+        assert!(Decoder::new(b"i1ei2e").setting(Settings::MaxBytes(6)).decode().is_ok());
+    }
+
+    #[test]
+    fn decode_prefix() {
+        let input = b"d8:msg_typei1e5:piecei0eeraw piece data";
+
+        let mut decoder = Decoder::new(&input[..]);
+        let (item, end) = decoder.decode_prefix().unwrap();
+
+        assert_eq!(item, vec![("msg_type".into(), 1.into()), ("piece".into(), 0.into())].into_iter().collect());
+        assert_eq!(&input[end..], b"raw piece data");
+
+        assert_eq!(Decoder::new(b"i1ei2e").decode_prefix(), Ok((1.into(), 3)));
+        assert_eq!(Decoder::new(b"").decode_prefix().map_err(|e| e.kind()), Err(UnexpectedEndOfBuffer));
+    }
+
+    #[test]
+    fn decode_single() {
+        assert_eq!(Decoder::new(b"3:foo").decode_single(), Ok("foo".into()));
+
+        let e = Decoder::new(b"3:foo\n").decode_single().unwrap_err();
+
+        assert_eq!(e.kind(), TrailingData);
+        assert_eq!(e.offset(), 5);
+        assert_eq!(e.bytes(), b"\n");
+    }
+
+    #[test]
+    fn error_context() {
+        let input = b"d4:infod5:filesld6:lengthi1ee1:xd6:lengthi001eeeee";
+
+        let e = Decoder::new(&input[..]).decode().unwrap_err();
+
+        assert_eq!(e.kind(), LeadingZeros);
+        assert_eq!(e.offset(), 41);
+        assert_eq!(
+            e.path(),
+            [
+                PathSegment::Key(b"info".to_vec()),
+                PathSegment::Key(b"files".to_vec()),
+                PathSegment::Index(2),
+                PathSegment::Key(b"length".to_vec())
+            ]
         );
+        assert_eq!(e.bytes(), b"i001e");
+        assert_eq!(e.to_string(), "leading zeros at offset 41 in `info.files[2].length` near \"i001e\"");
+    }
+
+    #[test]
+    fn unsorted_dictionary_context() {
+        let e = Decoder::new(b"l0:d3:foo0:3:bar0:ee").decode().unwrap_err();
+
+        assert_eq!(e.offset(), 11);
+        assert_eq!(e.path(), [PathSegment::Index(1)]);
+        assert_eq!(e.bytes(), b"bar");
+    }
+
+    #[test]
+    fn end_of_buffer_context() {
+        let e = Decoder::new(b"d3:foo10:bar").decode().unwrap_err();
+
+        assert_eq!(e.kind(), UnexpectedEndOfBuffer);
+        assert_eq!(e.offset(), 6);
+        assert_eq!(e.path(), [PathSegment::Key(b"foo".to_vec())]);
+        assert_eq!(e.bytes(), b"10:");
     }
 }
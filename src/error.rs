@@ -1,20 +1,152 @@
-#[derive(Debug, PartialEq, Eq)]
+use std::fmt;
+
+use crate::path::DisplayPath;
+use crate::PathSegment;
+
+/// The maximum number of offending bytes kept in a [`DecodeError`].
+const MAX_ERROR_BYTES: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 /// The error type for decode operations.
+///
+/// Besides the [`ErrorKind`], the error records where decoding went wrong: the byte offset
+/// in the input, the path of dictionary keys and list indices leading to the failing item,
+/// and the offending bytes.
 pub struct DecodeError {
     pub(crate) kind: ErrorKind,
+    offset: usize,
+    path: Vec<PathSegment>,
+    bytes: Vec<u8>,
+    message: Option<String>,
+    consumed: Option<usize>,
 }
 
 impl DecodeError {
+    /// Constructs a new `DecodeError` at specified offset.
+    pub(crate) fn new(kind: ErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            path: vec![],
+            bytes: vec![],
+            message: None,
+            consumed: None,
+        }
+    }
+
+    /// Constructs a new [`ErrorKind::Custom`] error with specified message.
+    #[cfg(feature = "serde")]
+    pub(crate) fn custom(message: String) -> Self {
+        Self {
+            message: Some(message),
+            ..Self::new(ErrorKind::Custom, 0)
+        }
+    }
+
+    /// Prepends a segment to the path to the failing item.
+    #[cfg(feature = "serde")]
+    pub(crate) fn within(mut self, segment: PathSegment) -> Self {
+        self.path.insert(0, segment);
+        self
+    }
+
+    /// Sets the path to the failing item.
+    pub(crate) fn with_path(self, path: Vec<PathSegment>) -> Self {
+        Self { path, ..self }
+    }
+
+    /// Sets the offending bytes, at most [`MAX_ERROR_BYTES`] of them are kept.
+    pub(crate) fn with_bytes(self, bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes[..bytes.len().min(MAX_ERROR_BYTES)].to_vec(),
+            ..self
+        }
+    }
+
+    /// Moves the error offset forward by `n` bytes.
+    pub(crate) fn shift(self, n: usize) -> Self {
+        Self {
+            offset: self.offset + n,
+            ..self
+        }
+    }
+
+    /// Sets the number of bytes consumed from the last fed chunk.
+    pub(crate) fn with_consumed(self, consumed: usize) -> Self {
+        Self {
+            consumed: Some(consumed),
+            ..self
+        }
+    }
+
+    /// Returns the kind of this error.
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Returns the offset of the input byte at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the path of dictionary keys and list indices leading to the failing item.
+    ///
+    /// The path is empty if the error occurred in a top level item.
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// Returns the offending bytes (if any), truncated to a reasonable length.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the message of an [`ErrorKind::Custom`] error.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns the number of bytes consumed from the last chunk fed to a
+    /// [`PushDecoder`](crate::PushDecoder).
+    ///
+    /// The bytes past this point do not belong to the failing item, decoding may be resumed
+    /// from there.
+    pub fn consumed(&self) -> Option<usize> {
+        self.consumed
+    }
 }
 
 impl std::error::Error for DecodeError {}
 
-impl std::fmt::Display for DecodeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.kind.fmt(f)
+impl From<std::io::Error> for DecodeError {
+    fn from(e: std::io::Error) -> Self {
+        let kind = match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => ErrorKind::UnexpectedEndOfBuffer,
+            kind => ErrorKind::Io(kind),
+        };
+
+        Self::new(kind, 0)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}", message)?,
+            // an i/o error is not caused by any particular byte
+            None if matches!(self.kind, ErrorKind::Io(_)) => write!(f, "{}", self.kind)?,
+            None => write!(f, "{} at offset {}", self.kind, self.offset)?,
+        }
+
+        if !self.path.is_empty() {
+            write!(f, " in `{}`", DisplayPath(&self.path))?;
+        }
+
+        if !self.bytes.is_empty() {
+            write!(f, " near \"{}\"", self.bytes.escape_ascii())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -33,24 +165,228 @@ pub enum ErrorKind {
     UnsortedDictionary,
     /// All dictionary keys must be byte strings.
     InvalidDictionaryKey,
+    /// A dictionary key occurs more than once.
+    DuplicateKey,
     /// Integers with leading zeros are not allowed.
     LeadingZeros,
     /// Negative zero is not allowed.
     NegativeZero,
     /// Data not valid for the operation were encountered.
     InvalidData,
+    /// There are bytes left after the item.
+    TrailingData,
+    /// An I/O error occurred while reading the input.
+    Io(std::io::ErrorKind),
+    /// Lists and dictionaries are nested deeper than allowed.
+    DepthLimitExceeded,
+    /// A string is longer than allowed.
+    StringLengthLimitExceeded,
+    /// The input contains more items than allowed.
+    ItemLimitExceeded,
+    /// The input is longer than allowed.
+    ByteLimitExceeded,
+    /// A decoded item does not match the type it is deserialized into.
+    ///
+    /// These errors carry a [message](DecodeError::message) and a path, but no offset.
+    Custom,
 }
 
-impl std::fmt::Display for ErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ErrorKind::UnexpectedByte(b) => write!(f, "unexpected byte `{}`", b),
             ErrorKind::UnexpectedEndOfBuffer => write!(f, "unexpected end of buffer"),
             ErrorKind::UnsortedDictionary => write!(f, "unsorted dictionary"),
             ErrorKind::InvalidDictionaryKey => write!(f, "invalid dictionary key"),
+            ErrorKind::DuplicateKey => write!(f, "duplicate dictionary key"),
             ErrorKind::LeadingZeros => write!(f, "leading zeros"),
             ErrorKind::NegativeZero => write!(f, "negative zero"),
             ErrorKind::InvalidData => write!(f, "invalid data"),
+            ErrorKind::TrailingData => write!(f, "trailing data"),
+            ErrorKind::Io(kind) => write!(f, "i/o error: {}", kind),
+            ErrorKind::DepthLimitExceeded => write!(f, "depth limit exceeded"),
+            ErrorKind::StringLengthLimitExceeded => write!(f, "string length limit exceeded"),
+            ErrorKind::ItemLimitExceeded => write!(f, "item limit exceeded"),
+            ErrorKind::ByteLimitExceeded => write!(f, "byte limit exceeded"),
+            ErrorKind::Custom => write!(f, "custom error"),
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The error type for parsing the text notation, see [`from_text`](crate::from_text).
+///
+/// Besides the [`ErrorKind`], the error records the line and the column at which parsing
+/// went wrong, both starting at 1.
+pub struct TextError {
+    kind: ErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl TextError {
+    /// Constructs a new `TextError` at specified byte offset of `text`.
+    pub(crate) fn new(kind: ErrorKind, text: &str, offset: usize) -> Self {
+        let before = &text.as_bytes()[..offset];
+        let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |pos| pos + 1);
+
+        Self {
+            kind,
+            offset,
+            line: before.iter().filter(|b| **b == b'\n').count() + 1,
+            // counts characters rather than bytes, skipping UTF-8 continuation bytes
+            column: before[line_start..].iter().filter(|b| (**b as i8) >= -0x40).count() + 1,
+        }
+    }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte offset at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the line at which the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column (in characters) at which the error occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl std::error::Error for TextError {}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.kind, self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The error type for reading torrent metainfo, see [`Metainfo`](crate::Metainfo).
+///
+/// Validation errors carry the path to the offending key, e.g. `info.files[3].length`.
+#[non_exhaustive]
+pub enum TorrentError {
+    /// The input is not valid bencode.
+    Decode(DecodeError),
+    /// The top level item is not a dictionary.
+    NotADictionary,
+    /// A required key is missing.
+    MissingKey(Vec<PathSegment>),
+    /// A value has a wrong type, is out of range, or is not valid UTF-8.
+    InvalidValue(Vec<PathSegment>),
+    /// The length of `info.pieces` is not a multiple of 20.
+    InvalidPieces(usize),
+    /// The `info` dictionary has both `length` and `files`, or neither of them.
+    InvalidFileLayout,
+}
+
+impl std::error::Error for TorrentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TorrentError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for TorrentError {
+    fn from(e: DecodeError) -> Self {
+        TorrentError::Decode(e)
+    }
+}
+
+impl fmt::Display for TorrentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TorrentError::Decode(e) => write!(f, "{}", e),
+            TorrentError::MissingKey(path) => write!(f, "missing key `{}`", DisplayPath(path)),
+            TorrentError::NotADictionary => write!(f, "metainfo is not a dictionary"),
+            TorrentError::InvalidValue(path) => write!(f, "invalid value of `{}`", DisplayPath(path)),
+            TorrentError::InvalidPieces(len) => write!(f, "length of pieces ({}) is not a multiple of 20", len),
+            TorrentError::InvalidFileLayout => write!(f, "info must have exactly one of `length` and `files`"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The error type for parsing magnet links, see [`Magnet`](crate::Magnet).
+#[non_exhaustive]
+pub enum MagnetError {
+    /// The link does not start with `magnet:?`.
+    NotMagnet,
+    /// A parameter is malformed, not percent-encoded correctly or not valid UTF-8.
+    InvalidParameter(String),
+    /// An `xt` parameter has a malformed info-hash.
+    InvalidInfoHash(String),
+    /// There is neither a v1 nor a v2 info-hash.
+    MissingInfoHash,
+}
+
+impl std::error::Error for MagnetError {}
+
+impl fmt::Display for MagnetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MagnetError::NotMagnet => write!(f, "not a magnet link"),
+            MagnetError::InvalidParameter(p) => write!(f, "invalid parameter `{}`", p),
+            MagnetError::InvalidInfoHash(h) => write!(f, "invalid info-hash `{}`", h),
+            MagnetError::MissingInfoHash => write!(f, "missing info-hash"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+/// The error type for serialization.
+pub enum EncodeError {
+    /// An I/O error occurred while writing the output.
+    Io(std::io::Error),
+    /// The value has no bencode representation, e.g. a float, or a `None` outside
+    /// of a dictionary.
+    UnsupportedValue(&'static str),
+    /// Dictionary keys must serialize to strings or integers.
+    InvalidDictionaryKey,
+    /// A dictionary key occurs more than once.
+    DuplicateKey,
+    /// An error reported by a `Serialize` implementation.
+    Custom(String),
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for EncodeError {
+    fn from(e: std::io::Error) -> Self {
+        EncodeError::Io(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Io(e) => write!(f, "i/o error: {}", e),
+            EncodeError::UnsupportedValue(v) => write!(f, "unsupported value: {}", v),
+            EncodeError::InvalidDictionaryKey => write!(f, "invalid dictionary key"),
+            EncodeError::DuplicateKey => write!(f, "duplicate dictionary key"),
+            EncodeError::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
\ No newline at end of file
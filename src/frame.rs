@@ -0,0 +1,255 @@
+use crate::decode::{exceeds, is_number_byte, parse_length_lenient, Leniency, Limits, MAX_LENGTH_DIGITS};
+
+/// Framing status.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Status {
+    /// More bytes are required to complete the current item.
+    Incomplete,
+    /// A complete top level item was framed.
+    Complete,
+    /// The framed bytes are not valid bencode.
+    ///
+    /// The bytes consumed so far are enough for a [`Decoder`](crate::Decoder) to report the
+    /// exact error.
+    Invalid,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum State {
+    /// Expecting the beginning of an item (or the end of a list or a dictionary).
+    Item,
+    /// Reading the length of a string.
+    Length,
+    /// Reading the bytes of a string.
+    Bytes(usize),
+    /// Reading an integer.
+    Integer,
+}
+
+/// Finds the boundaries of top level items in a byte stream.
+///
+/// The framer does not decode anything, it only tracks the structure of the input
+/// so that a complete item can be handed over to a [`Decoder`](crate::Decoder).
+/// It follows the same rules the decoder does, so decoding the framed bytes produces
+/// exactly the same result (or error) as decoding the whole input would.
+///
+/// Limits are checked as well, so that the framer never buffers more than the decoder
+/// would accept, and so is leniency.
+#[derive(Debug, Clone)]
+pub(crate) struct Framer {
+    limits: Limits,
+    leniency: Leniency,
+    state: State,
+    depth: usize,
+    length: Vec<u8>,
+    items: usize,
+    total: usize,
+}
+
+impl Framer {
+    /// Constructs a new `Framer` with specified limits and leniency.
+    pub(crate) fn new(limits: Limits, leniency: Leniency) -> Self {
+        Self {
+            limits,
+            leniency,
+            state: State::Item,
+            depth: 0,
+            length: vec![],
+            items: 0,
+            total: 0,
+        }
+    }
+
+    /// Feeds a chunk of bytes and returns the number of bytes consumed.
+    ///
+    /// Consumption stops right after a complete item or an invalid byte, so any bytes
+    /// past that point belong to whatever follows.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> (usize, Status) {
+        let mut consumed = 0;
+
+        while consumed < chunk.len() {
+            let status = if let State::Bytes(remaining) = self.state {
+                let mut n = remaining.min(chunk.len() - consumed);
+
+                if let Some(limit) = self.limits.bytes {
+                    n = n.min(limit + 1 - self.total);
+                }
+
+                consumed += n;
+                self.total += n;
+
+                if n == remaining {
+                    self.item_done()
+                } else {
+                    self.state = State::Bytes(remaining - n);
+
+                    None
+                }
+            } else {
+                let byte = chunk[consumed];
+
+                consumed += 1;
+                self.total += 1;
+
+                self.step(byte)
+            };
+
+            if exceeds(self.total, self.limits.bytes) {
+                return (consumed, Status::Invalid);
+            }
+
+            if let Some(status) = status {
+                return (consumed, status);
+            }
+        }
+
+        (consumed, Status::Incomplete)
+    }
+
+    /// Processes a single byte.
+    fn step(&mut self, byte: u8) -> Option<Status> {
+        if self.state == State::Item && self.leniency.whitespace && byte.is_ascii_whitespace() {
+            return None;
+        }
+
+        if self.state == State::Item && byte != b'e' {
+            self.items += 1;
+
+            if exceeds(self.items, self.limits.items) {
+                return Some(Status::Invalid);
+            }
+        }
+
+        match (self.state, byte) {
+            (State::Item, b'+') if self.leniency.plus_sign => {
+                self.length.push(byte);
+                self.state = State::Length;
+            },
+            (State::Item, b'0'..=b'9') => {
+                self.length.push(byte);
+                self.state = State::Length;
+            },
+            (State::Item, b'i') => self.state = State::Integer,
+            (State::Item, b'l' | b'd') => {
+                self.depth += 1;
+
+                if exceeds(self.depth, self.limits.depth) {
+                    return Some(Status::Invalid);
+                }
+            },
+            (State::Item, b'e') if self.depth > 0 => {
+                self.depth -= 1;
+
+                return self.item_done();
+            },
+            (State::Item, _) => return Some(Status::Invalid),
+            (State::Length, b':') => {
+                let length = parse_length_lenient(&self.length, self.leniency, |_| {});
+
+                self.length.clear();
+
+                match length {
+                    Ok(length) if exceeds(length, self.limits.string_length) => return Some(Status::Invalid),
+                    Ok(0) => return self.item_done(),
+                    Ok(length) => self.state = State::Bytes(length),
+                    Err(_) => return Some(Status::Invalid),
+                }
+            },
+            (State::Length, _) if is_number_byte(byte) && self.length.len() < MAX_LENGTH_DIGITS => self.length.push(byte),
+            (State::Integer, b'e') => return self.item_done(),
+            (State::Integer, _) if is_number_byte(byte) => {},
+            (State::Length | State::Integer, _) => return Some(Status::Invalid),
+            (State::Bytes(_), _) => unreachable!("string bytes are consumed in bulk"),
+        }
+
+        None
+    }
+
+    /// Finishes an item, returns [`Status::Complete`] if it was a top level one.
+    fn item_done(&mut self) -> Option<Status> {
+        self.state = State::Item;
+
+        if self.depth == 0 {
+            Some(Status::Complete)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Framer, Status};
+    use crate::decode::{Leniency, Limits};
+
+    fn frame(input: &[u8]) -> (usize, Status) {
+        Framer::new(Limits::default(), Leniency::default()).feed(input)
+    }
+
+    #[test]
+    fn complete_items() {
+        assert_eq!(frame(b"3:foo3:bar"), (5, Status::Complete));
+        assert_eq!(frame(b"i42ei1e"), (4, Status::Complete));
+        assert_eq!(frame(b"d3:fooli1e0:eee"), (14, Status::Complete));
+    }
+
+    #[test]
+    fn incomplete_items() {
+        assert_eq!(frame(b"d3:foo"), (6, Status::Incomplete));
+        assert_eq!(frame(b"10:abc"), (6, Status::Incomplete));
+    }
+
+    #[test]
+    fn invalid_items() {
+        assert_eq!(frame(b"lxe"), (2, Status::Invalid));
+        assert_eq!(frame(b"e"), (1, Status::Invalid));
+        assert_eq!(frame(b"01:a"), (3, Status::Invalid));
+        assert_eq!(frame(b"1hello:"), (2, Status::Invalid));
+        assert_eq!(frame(b"iabce"), (2, Status::Invalid));
+        assert_eq!(frame(&[b'1'; 100]), (21, Status::Invalid));
+    }
+
+    #[test]
+    fn limits() {
+        let limits = |f: fn(&mut Limits)| {
+            let mut limits = Limits::default();
+            f(&mut limits);
+            Framer::new(limits, Leniency::default())
+        };
+
+        assert_eq!(limits(|l| l.depth = Some(2)).feed(b"llleee"), (3, Status::Invalid));
+        assert_eq!(limits(|l| l.string_length = Some(2)).feed(b"3:foo"), (2, Status::Invalid));
+        assert_eq!(limits(|l| l.items = Some(2)).feed(b"li1ei2ee"), (5, Status::Invalid));
+        assert_eq!(limits(|l| l.bytes = Some(4)).feed(b"10:abcdefghij"), (5, Status::Invalid));
+        assert_eq!(limits(|l| l.bytes = Some(5)).feed(b"3:foo"), (5, Status::Complete));
+    }
+
+    #[test]
+    fn leniency() {
+        let leniency = Leniency {
+            plus_sign: true,
+            leading_zeros: true,
+            whitespace: true,
+        };
+
+        assert_eq!(Framer::new(Limits::default(), leniency).feed(b" l +01:a i-0e e 3:foo"), (15, Status::Complete));
+        assert_eq!(frame(b" l"), (1, Status::Invalid));
+        assert_eq!(frame(b"+1:a"), (1, Status::Invalid));
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let input = b"d4:spaml1:a1:bee";
+        let mut framer = Framer::new(Limits::default(), Leniency::default());
+
+        for (i, byte) in input.iter().enumerate() {
+            let expected = if i == input.len() - 1 {
+                Status::Complete
+            } else {
+                Status::Incomplete
+            };
+
+            assert_eq!(framer.feed(&[*byte]), (1, expected));
+        }
+    }
+}
@@ -0,0 +1,489 @@
+use crate::encode::Bencode;
+use crate::{Decoder, Info, Item, PathSegment, Settings, TorrentError};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The info-hash of a torrent, the hash of the raw bytes of its `info` dictionary.
+pub struct InfoHash<const N: usize> {
+    /// The hash.
+    pub hash: [u8; N],
+    /// `false` if the `info` dictionary is not in canonical form.
+    ///
+    /// Re-encoding such a dictionary, e.g. with [`Info::to_item`], gives different bytes
+    /// and thus a different hash. See also [`canonicalize`](crate::canonicalize).
+    pub canonical: bool,
+}
+
+/// Computes the BitTorrent v1 info-hash, the SHA-1 hash of the `info` dictionary.
+///
+/// The exact bytes of the `info` dictionary in the input are hashed. Torrents with unsorted
+/// dictionaries and other deviations from the canonical form are accepted, and reported as
+/// such.
+///
+/// ```
+/// use yabel::info_hash_v1;
+///
+/// let hash = info_hash_v1(b"d8:announce1:a4:infod4:name3:fooee").unwrap();
+///
+/// assert_eq!(hash.hash, yabel::sha1(b"d4:name3:fooe"));
+/// assert!(hash.canonical);
+///
+/// let hash = info_hash_v1(b"d4:infod1:bi1e1:ai2eee").unwrap();
+///
+/// assert_eq!(hash.hash, yabel::sha1(b"d1:bi1e1:ai2ee"));
+/// assert!(!hash.canonical);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the input is malformed, or if it has no `info` dictionary.
+pub fn info_hash_v1(bytes: &[u8]) -> Result<InfoHash<20>, TorrentError> {
+    let (_, info, canonical) = raw_info(bytes)?;
+
+    Ok(InfoHash {
+        hash: sha1(info),
+        canonical,
+    })
+}
+
+/// Computes the BitTorrent v2 info-hash, the SHA-256 hash of the `info` dictionary.
+///
+/// See [`info_hash_v1`] for details.
+///
+/// # Errors
+///
+/// Returns an error if the input is malformed, or if it has no `info` dictionary.
+pub fn info_hash_v2(bytes: &[u8]) -> Result<InfoHash<32>, TorrentError> {
+    let (_, info, canonical) = raw_info(bytes)?;
+
+    Ok(InfoHash {
+        hash: sha256(info),
+        canonical,
+    })
+}
+
+/// Decodes a torrent leniently, and returns it together with the raw bytes of its top level
+/// `info` dictionary and whether they are canonical.
+pub(crate) fn raw_info(bytes: &[u8]) -> Result<(Item<'_>, &[u8], bool), TorrentError> {
+    let mut decoder = Decoder::new(bytes).setting(Settings::Lenient).capture(b"info");
+
+    let item = decoder.decode_single()?;
+
+    if !item.is_dict() {
+        return Err(TorrentError::NotADictionary);
+    }
+
+    let path = [PathSegment::from("info")];
+
+    // the last value of a duplicate key wins
+    let info = decoder
+        .captures()
+        .iter()
+        .rev()
+        .find(|c| c.value == path)
+        .ok_or_else(|| TorrentError::MissingKey(path.to_vec()))?;
+
+    if !item["info"].is_dict() {
+        return Err(TorrentError::InvalidValue(path.to_vec()));
+    }
+
+    let canonical = !decoder.deviations().iter().any(|d| info.span.contains(&d.offset));
+
+    let info = info.bytes;
+
+    Ok((item, info, canonical))
+}
+
+impl Info {
+    /// Computes the BitTorrent v1 info-hash of the encoded `info` dictionary.
+    ///
+    /// This is the same hash as the one computed by [`info_hash_v1`] from the original
+    /// bytes only if those were canonical.
+    pub fn hash_v1(&self) -> [u8; 20] {
+        sha1(&self.to_item().encode())
+    }
+
+    /// Computes the BitTorrent v2 info-hash of the encoded `info` dictionary.
+    ///
+    /// This is the same hash as the one computed by [`info_hash_v2`] from the original
+    /// bytes only if those were canonical.
+    pub fn hash_v2(&self) -> [u8; 32] {
+        sha256(&self.to_item().encode())
+    }
+}
+
+/// Computes the SHA-1 hash of `data`.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Computes the SHA-256 hash of `data`.
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Splits the input into 64 byte blocks and pads the last one, the same way for SHA-1 and
+/// SHA-256.
+#[derive(Clone, Debug)]
+struct Blocks {
+    buffer: [u8; 64],
+    buffered: usize,
+    length: u64,
+}
+
+impl Blocks {
+    fn new() -> Self {
+        Self {
+            buffer: [0; 64],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+
+        if self.buffered > 0 {
+            let n = data.len().min(64 - self.buffered);
+
+            self.buffer[self.buffered..self.buffered + n].copy_from_slice(&data[..n]);
+            self.buffered += n;
+            data = &data[n..];
+
+            if self.buffered < 64 {
+                return;
+            }
+
+            compress(&self.buffer);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(64);
+
+        for block in &mut blocks {
+            compress(block.try_into().expect("the block is 64 bytes long"));
+        }
+
+        let rest = blocks.remainder();
+
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// Appends the padding and the message length in bits.
+    fn finish(mut self, mut compress: impl FnMut(&[u8; 64])) {
+        let bits = self.length.wrapping_mul(8);
+
+        self.buffer[self.buffered] = 0x80;
+        self.buffer[self.buffered + 1..].fill(0);
+
+        if self.buffered >= 56 {
+            compress(&self.buffer);
+            self.buffer.fill(0);
+        }
+
+        self.buffer[56..].copy_from_slice(&bits.to_be_bytes());
+
+        compress(&self.buffer);
+    }
+}
+
+/// Reads a block as big-endian words.
+fn words(block: &[u8; 64]) -> [u32; 16] {
+    let mut words = [0; 16];
+
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().expect("the chunk is 4 bytes long"));
+    }
+
+    words
+}
+
+/// Writes the state as big-endian bytes.
+fn digest<const W: usize, const N: usize>(state: [u32; W]) -> [u8; N] {
+    let mut digest = [0; N];
+
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+
+    digest
+}
+
+#[derive(Clone, Debug)]
+/// Incremental SHA-1 hasher.
+///
+/// ```
+/// use yabel::{sha1, Sha1};
+///
+/// let mut hasher = Sha1::new();
+///
+/// hasher.update(b"hello ");
+/// hasher.update(b"world");
+///
+/// assert_eq!(hasher.finish(), sha1(b"hello world"));
+/// ```
+pub struct Sha1 {
+    state: [u32; 5],
+    blocks: Blocks,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha1 {
+    /// Constructs a new `Sha1` hasher.
+    pub fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            blocks: Blocks::new(),
+        }
+    }
+
+    /// Feeds `data` into the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+
+        self.blocks.update(data, |block| sha1_compress(state, block));
+    }
+
+    /// Returns the hash of all the data fed so far.
+    pub fn finish(self) -> [u8; 20] {
+        let mut state = self.state;
+
+        self.blocks.finish(|block| sha1_compress(&mut state, block));
+
+        digest(state)
+    }
+}
+
+fn sha1_compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut w = [0; 80];
+
+    w[..16].copy_from_slice(&words(block));
+
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    for (i, w) in w.iter().enumerate() {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | (!b & d), 0x5a827999),
+            20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+
+        let t = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*w);
+
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = t;
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+/// The SHA-256 round constants.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+#[derive(Clone, Debug)]
+/// Incremental SHA-256 hasher.
+///
+/// See [`Sha1`] for an example.
+pub struct Sha256 {
+    state: [u32; 8],
+    blocks: Blocks,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha256 {
+    /// Constructs a new `Sha256` hasher.
+    pub fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+            ],
+            blocks: Blocks::new(),
+        }
+    }
+
+    /// Feeds `data` into the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+
+        self.blocks.update(data, |block| sha256_compress(state, block));
+    }
+
+    /// Returns the hash of all the data fed so far.
+    pub fn finish(self) -> [u8; 32] {
+        let mut state = self.state;
+
+        self.blocks.finish(|block| sha256_compress(&mut state, block));
+
+        digest(state)
+    }
+}
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0; 64];
+
+    w[..16].copy_from_slice(&words(block));
+
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (k, w) in K.iter().zip(w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(*k).wrapping_add(w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::hex;
+    use crate::Metainfo;
+
+    #[test]
+    fn sha1_vectors() {
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(hex(&sha1(&[b'a'; 1_000_000])), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            hex(&sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            hex(&sha256(&[b'a'; 1_000_000])),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn incremental() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        // split at every length around the block and padding boundaries
+        for split in [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 999, 1000] {
+            let mut sha1_hasher = Sha1::new();
+            let mut sha256_hasher = Sha256::new();
+
+            for part in [&data[..split], &data[split..]] {
+                sha1_hasher.update(part);
+                sha256_hasher.update(part);
+            }
+
+            assert_eq!(sha1_hasher.finish(), sha1(&data), "split = {}", split);
+            assert_eq!(sha256_hasher.finish(), sha256(&data), "split = {}", split);
+        }
+    }
+
+    #[test]
+    fn info_hashes() {
+        let bytes = b"d8:announce9:http://a/4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee";
+        let info = b"d6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:e";
+
+        let v1 = info_hash_v1(bytes).unwrap();
+        let v2 = info_hash_v2(bytes).unwrap();
+
+        assert_eq!(v1, InfoHash { hash: sha1(info), canonical: true });
+        assert_eq!(v2, InfoHash { hash: sha256(info), canonical: true });
+
+        let metainfo = Metainfo::from_bytes(bytes).unwrap();
+
+        assert_eq!(metainfo.info.hash_v1(), v1.hash);
+        assert_eq!(metainfo.info.hash_v2(), v2.hash);
+    }
+
+    #[test]
+    fn non_canonical_info() {
+        // deviations outside of `info` do not matter
+        let bytes = b"d 4:infod4:name1:ae 8:announcei01ee";
+
+        assert_eq!(info_hash_v1(bytes).unwrap(), InfoHash { hash: sha1(b"d4:name1:ae"), canonical: true });
+
+        for info in [&b"d4:name1:a1:ai1ee"[..], b"d4:namei01ee", b"d4:name 1:ae", b"d4:name+1:ae"] {
+            let bytes = [&b"d4:info"[..], info, b"e"].concat();
+
+            assert_eq!(info_hash_v1(&bytes).unwrap(), InfoHash { hash: sha1(info), canonical: false });
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(info_hash_v1(b"d4:info"), Err(TorrentError::Decode(_))));
+        assert_eq!(info_hash_v1(b"le"), Err(TorrentError::NotADictionary));
+        assert_eq!(info_hash_v1(b"de"), Err(TorrentError::MissingKey(vec!["info".into()])));
+        assert_eq!(info_hash_v2(b"d4:infoi1ee"), Err(TorrentError::InvalidValue(vec!["info".into()])));
+
+        // nested `info` keys are not the info dictionary
+        assert_eq!(info_hash_v1(b"d1:ad4:infodeee"), Err(TorrentError::MissingKey(vec!["info".into()])));
+    }
+}
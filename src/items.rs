@@ -1,6 +1,8 @@
-use std::borrow::Cow;
+use std::borrow::{Borrow, Cow};
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
+use std::io::{self, Write};
+use std::ops::Index;
 use std::{fmt, str};
 
 use crate::encode::Bencode;
@@ -12,10 +14,16 @@ pub enum Item<'a> {
     String(BString<'a>),
     /// Integer.
     Integer(BInteger),
+    /// Integer out of the `i64` range.
+    BigInteger(BBigInteger),
     /// List.
     List(BList<'a>),
     /// Dictionary.
     Dictionary(BDictionary<'a>),
+    /// Dictionary that keeps its keys in insertion order.
+    ///
+    /// See [`Settings::PreserveDictionaryOrder`](crate::Settings::PreserveDictionaryOrder).
+    OrderedDictionary(BOrderedDictionary<'a>),
 }
 
 #[derive(Default, Ord, PartialOrd, PartialEq, Eq, Clone)]
@@ -26,6 +34,14 @@ pub struct BString<'a>(pub Cow<'a, [u8]>);
 /// The integer type.
 pub struct BInteger(pub i64);
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+/// The integer type for values out of the `i64` range.
+///
+/// Bencode places no limit on the size of integers. Values that do not fit into
+/// [`BInteger`] are kept as decimal digits in canonical form: an optional `-` sign followed
+/// by digits without leading zeros.
+pub struct BBigInteger(String);
+
 #[derive(Default, Debug, Eq, PartialEq, Clone)]
 /// The list type.
 pub struct BList<'a>(pub Vec<Item<'a>>);
@@ -34,6 +50,13 @@ pub struct BList<'a>(pub Vec<Item<'a>>);
 /// The dictionary type.
 pub struct BDictionary<'a>(pub BTreeMap<BString<'a>, Item<'a>>);
 
+#[derive(Default, Debug, Eq, PartialEq, Clone)]
+/// The dictionary type that keeps its keys in insertion order.
+///
+/// Unlike [`BDictionary`], this type is encoded exactly in the order its keys are stored,
+/// which makes byte-for-byte round trips of unsorted dictionaries possible.
+pub struct BOrderedDictionary<'a>(pub Vec<(BString<'a>, Item<'a>)>);
+
 impl<'a> Item<'a> {
     /// Returns a string if the current variant is a string.
     pub fn string(self) -> Option<BString<'a>> {
@@ -66,54 +89,655 @@ impl<'a> Item<'a> {
             _ => None,
         }
     }
+
+    /// Returns an ordered dictionary if the current variant is an ordered dictionary.
+    pub fn ordered_dictionary(self) -> Option<BOrderedDictionary<'a>> {
+        match self {
+            Item::OrderedDictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of the string if the current variant is a string.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Item::String(s) => Some(&s.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if the current variant is a string and it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Item::String(s) => s.as_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the current variant is an integer and it fits into `i64`.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Item::Integer(i) => Some(i.0),
+            Item::BigInteger(i) => i.to_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the current variant is an integer and it fits into `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Item::Integer(i) => u64::try_from(i.0).ok(),
+            Item::BigInteger(i) => i.to_u64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the current variant is an integer and it fits into `i128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Item::Integer(i) => Some(i128::from(i.0)),
+            Item::BigInteger(i) => i.to_i128(),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the big integer if the current variant is a big integer.
+    pub fn as_big_int(&self) -> Option<&BBigInteger> {
+        match self {
+            Item::BigInteger(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the list if the current variant is a list.
+    pub fn as_list(&self) -> Option<&BList<'a>> {
+        match self {
+            Item::List(l) if !self.is_null() => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the dictionary if the current variant is a dictionary.
+    pub fn as_dict(&self) -> Option<&BDictionary<'a>> {
+        match self {
+            Item::Dictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the ordered dictionary if the current variant is an ordered
+    /// dictionary.
+    pub fn as_ordered_dict(&self) -> Option<&BOrderedDictionary<'a>> {
+        match self {
+            Item::OrderedDictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the bytes of the string if the current variant is a string.
+    pub fn as_bytes_mut(&mut self) -> Option<&mut Cow<'a, [u8]>> {
+        match self {
+            Item::String(s) => Some(&mut s.0),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the integer if the current variant is an integer.
+    pub fn as_int_mut(&mut self) -> Option<&mut i64> {
+        match self {
+            Item::Integer(i) => Some(&mut i.0),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the list if the current variant is a list.
+    pub fn as_list_mut(&mut self) -> Option<&mut BList<'a>> {
+        match self {
+            Item::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the dictionary if the current variant is a dictionary.
+    pub fn as_dict_mut(&mut self) -> Option<&mut BDictionary<'a>> {
+        match self {
+            Item::Dictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the ordered dictionary if the current variant is an
+    /// ordered dictionary.
+    pub fn as_ordered_dict_mut(&mut self) -> Option<&mut BOrderedDictionary<'a>> {
+        match self {
+            Item::OrderedDictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the current variant is a string.
+    pub fn is_bytes(&self) -> bool {
+        matches!(self, Item::String(_))
+    }
+
+    /// Returns `true` if the current variant is a string and it is valid UTF-8.
+    pub fn is_str(&self) -> bool {
+        self.as_str().is_some()
+    }
+
+    /// Returns `true` if the current variant is an integer or a big integer.
+    pub fn is_int(&self) -> bool {
+        matches!(self, Item::Integer(_) | Item::BigInteger(_))
+    }
+
+    /// Returns `true` if the current variant is a big integer.
+    pub fn is_big_int(&self) -> bool {
+        matches!(self, Item::BigInteger(_))
+    }
+
+    /// Returns `true` if the current variant is a list.
+    pub fn is_list(&self) -> bool {
+        matches!(self, Item::List(_)) && !self.is_null()
+    }
+
+    /// Returns `true` if the current variant is a dictionary.
+    pub fn is_dict(&self) -> bool {
+        matches!(self, Item::Dictionary(_))
+    }
+
+    /// Returns `true` if the current variant is an ordered dictionary.
+    pub fn is_ordered_dict(&self) -> bool {
+        matches!(self, Item::OrderedDictionary(_))
+    }
+
+    /// Returns `true` if the item is the null sentinel returned by a missed lookup through
+    /// the [`Index`] implementations.
+    ///
+    /// The sentinel is an empty list told apart from real ones by its address, so all the
+    /// `as_*` and `is_*` accessors treat it as absent.
+    ///
+    /// ```
+    /// use yabel::Decoder;
+    ///
+    /// let item = Decoder::new(b"d3:fooli1eee").decode_single().unwrap();
+    ///
+    /// assert_eq!(item["foo"][0].as_int(), Some(1));
+    /// assert!(item["bar"][0].is_null());
+    /// assert!(!item["bar"].is_list());
+    /// ```
+    pub fn is_null(&self) -> bool {
+        std::ptr::eq(self, &NULL)
+    }
+
+    /// Converts the item into one that owns all of its data, so it no longer borrows
+    /// from the decoded buffer.
+    ///
+    /// ```
+    /// use yabel::{Decoder, Item};
+    ///
+    /// let item: Item<'static> = {
+    ///     let buffer = b"d3:fooli1eee".to_vec();
+    ///
+    ///     Decoder::new(&buffer).decode_single().unwrap().into_owned()
+    /// };
+    ///
+    /// assert_eq!(item["foo"][0].as_int(), Some(1));
+    /// ```
+    pub fn into_owned(self) -> Item<'static> {
+        match self {
+            Item::String(s) => Item::String(s.into_owned()),
+            Item::Integer(i) => Item::Integer(i),
+            Item::BigInteger(i) => Item::BigInteger(i),
+            Item::List(l) => Item::List(l.into_owned()),
+            Item::Dictionary(d) => Item::Dictionary(d.into_owned()),
+            Item::OrderedDictionary(d) => Item::OrderedDictionary(d.into_owned()),
+        }
+    }
+
+    /// Returns a copy of the item that owns all of its data.
+    pub fn to_owned_item(&self) -> Item<'static> {
+        match self {
+            Item::String(s) => Item::String(s.to_owned_item()),
+            Item::Integer(i) => Item::Integer(i.clone()),
+            Item::BigInteger(i) => Item::BigInteger(i.clone()),
+            Item::List(l) => Item::List(l.to_owned_item()),
+            Item::Dictionary(d) => Item::Dictionary(d.to_owned_item()),
+            Item::OrderedDictionary(d) => Item::OrderedDictionary(d.to_owned_item()),
+        }
+    }
 }
 
-impl<'a> Bencode for Item<'a> {
-    fn encode(self) -> Vec<u8> {
+/// The item returned by the [`Index`] implementations when nothing is found.
+///
+/// It is told apart from a real empty list by its address, see [`Item::is_null`].
+static NULL: Item<'static> = Item::List(BList(Vec::new()));
+
+impl<'a> Index<&str> for Item<'a> {
+    type Output = Item<'a>;
+
+    /// Returns the value corresponding to the key if the item is a dictionary and has the
+    /// key, or the null sentinel otherwise, so that chained lookups never panic.
+    fn index(&self, key: &str) -> &Self::Output {
+        let value = match self {
+            Item::Dictionary(d) => d.0.get(key.as_bytes()),
+            Item::OrderedDictionary(d) => d.get_key(key.as_bytes()),
+            _ => None,
+        };
+
+        value.unwrap_or(&NULL)
+    }
+}
+
+impl<'a> Index<usize> for Item<'a> {
+    type Output = Item<'a>;
+
+    /// Returns the element at the index if the item is a list and the index is in bounds,
+    /// or the null sentinel otherwise.
+    fn index(&self, index: usize) -> &Self::Output {
+        self.as_list().and_then(|l| l.0.get(index)).unwrap_or(&NULL)
+    }
+}
+
+impl<'a> BOrderedDictionary<'a> {
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// See [`BOrderedDictionary::get`] for lookups by path.
+    pub(crate) fn get_key(&self, key: &[u8]) -> Option<&Item<'a>> {
+        self.0.iter().find(|(k, _)| k.0[..] == *key).map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    pub(crate) fn get_key_mut(&mut self, key: &[u8]) -> Option<&mut Item<'a>> {
+        self.0.iter_mut().find(|(k, _)| k.0[..] == *key).map(|(_, v)| v)
+    }
+
+    /// Inserts a key-value pair.
+    ///
+    /// If the dictionary already has this key, the value is replaced in place and the old
+    /// value is returned. Otherwise the pair is appended to the end.
+    pub fn insert(&mut self, key: BString<'a>, value: Item<'a>) -> Option<Item<'a>> {
+        match self.get_key_mut(&key.0) {
+            Some(v) => Some(std::mem::replace(v, value)),
+            None => {
+                self.0.push((key, value));
+                None
+            },
+        }
+    }
+
+    /// Removes a key, returning its value if the key was in the dictionary.
+    pub fn remove(&mut self, key: &[u8]) -> Option<Item<'a>> {
+        self.0.iter().position(|(k, _)| k.0[..] == *key).map(|i| self.0.remove(i).1)
+    }
+
+    /// Returns an iterator over the keys, in insertion order.
+    pub fn keys(&self) -> impl Iterator<Item = &BString<'a>> {
+        self.0.iter().map(|(k, _)| k)
+    }
+
+    /// Returns the number of elements in the dictionary.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the dictionary contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> BString<'a> {
+    /// Converts the string into one that owns its bytes.
+    pub fn into_owned(self) -> BString<'static> {
+        BString(Cow::Owned(self.0.into_owned()))
+    }
+
+    /// Returns a copy of the string that owns its bytes.
+    pub fn to_owned_item(&self) -> BString<'static> {
+        BString(Cow::Owned(self.0.to_vec()))
+    }
+}
+
+impl<'a> BList<'a> {
+    /// Converts the list into one that owns all of its data.
+    pub fn into_owned(self) -> BList<'static> {
+        BList(self.0.into_iter().map(Item::into_owned).collect())
+    }
+
+    /// Returns a copy of the list that owns all of its data.
+    pub fn to_owned_item(&self) -> BList<'static> {
+        BList(self.0.iter().map(Item::to_owned_item).collect())
+    }
+}
+
+impl<'a> BDictionary<'a> {
+    /// Converts the dictionary into one that owns all of its data.
+    pub fn into_owned(self) -> BDictionary<'static> {
+        BDictionary(self.0.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect())
+    }
+
+    /// Returns a copy of the dictionary that owns all of its data.
+    pub fn to_owned_item(&self) -> BDictionary<'static> {
+        BDictionary(self.0.iter().map(|(k, v)| (k.to_owned_item(), v.to_owned_item())).collect())
+    }
+}
+
+impl<'a> BOrderedDictionary<'a> {
+    /// Converts the dictionary into one that owns all of its data.
+    pub fn into_owned(self) -> BOrderedDictionary<'static> {
+        BOrderedDictionary(self.0.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect())
+    }
+
+    /// Returns a copy of the dictionary that owns all of its data.
+    pub fn to_owned_item(&self) -> BOrderedDictionary<'static> {
+        BOrderedDictionary(self.0.iter().map(|(k, v)| (k.to_owned_item(), v.to_owned_item())).collect())
+    }
+}
+
+impl<'a> Item<'a> {
+    /// Writes the encoded item into `w`.
+    ///
+    /// Every byte is written exactly once, without intermediate allocations. As the item
+    /// is written in many small pieces, consider wrapping unbuffered writers into a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
         match self {
-            Item::String(s) => s.encode(),
-            Item::Integer(i) => i.encode(),
-            Item::List(l) => l.encode(),
-            Item::Dictionary(d) => d.encode(),
+            Item::String(s) => s.encode_to(w),
+            Item::Integer(i) => i.encode_to(w),
+            Item::BigInteger(i) => i.encode_to(w),
+            Item::List(l) => l.encode_to(w),
+            Item::Dictionary(d) => d.encode_to(w),
+            Item::OrderedDictionary(d) => d.encode_to(w),
+        }
+    }
+
+    /// Returns the length of the encoded item.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Item::String(s) => s.encoded_len(),
+            Item::Integer(i) => i.encoded_len(),
+            Item::BigInteger(i) => i.encoded_len(),
+            Item::List(l) => l.encoded_len(),
+            Item::Dictionary(d) => d.encoded_len(),
+            Item::OrderedDictionary(d) => d.encoded_len(),
+        }
+    }
+}
+
+impl<'a> BString<'a> {
+    /// Returns the string if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+
+    /// Writes the encoded string into `w`.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}:", self.0.len())?;
+        w.write_all(&self.0)
+    }
+
+    /// Returns the length of the encoded string.
+    pub fn encoded_len(&self) -> usize {
+        decimal_len(self.0.len() as u64) + 1 + self.0.len()
+    }
+}
+
+impl BInteger {
+    /// Writes the encoded integer into `w`.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "i{}e", self.0)
+    }
+
+    /// Returns the length of the encoded integer.
+    pub fn encoded_len(&self) -> usize {
+        decimal_len(self.0.unsigned_abs()) + usize::from(self.0 < 0) + 2
+    }
+}
+
+impl BBigInteger {
+    /// Creates a big integer from its decimal digits.
+    ///
+    /// Returns `None` unless `digits` is an optional `-` sign followed by digits without
+    /// leading zeros, and not a negative zero.
+    ///
+    /// ```
+    /// use yabel::BBigInteger;
+    ///
+    /// let i = BBigInteger::new("18446744073709551616").unwrap();
+    ///
+    /// assert_eq!(i.to_u64(), None);
+    /// assert_eq!(i.to_i128(), Some(1 << 64));
+    ///
+    /// assert!(BBigInteger::new("007").is_none());
+    /// ```
+    pub fn new(digits: &str) -> Option<Self> {
+        let magnitude = digits.strip_prefix('-').unwrap_or(digits);
+
+        let valid = match magnitude.as_bytes() {
+            [] => false,
+            [b'0'] => magnitude.len() == digits.len(),
+            [b'0', ..] => false,
+            bytes => bytes.iter().all(u8::is_ascii_digit),
+        };
+
+        valid.then(|| BBigInteger(digits.to_owned()))
+    }
+
+    /// Returns the decimal digits of the integer.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if the integer is negative.
+    pub fn is_negative(&self) -> bool {
+        self.0.starts_with('-')
+    }
+
+    /// Converts the integer into `i64`, if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.parse().ok()
+    }
+
+    /// Converts the integer into `u64`, if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+
+    /// Converts the integer into `i128`, if it fits.
+    pub fn to_i128(&self) -> Option<i128> {
+        self.0.parse().ok()
+    }
+
+    /// Converts the integer into `u128`, if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        self.0.parse().ok()
+    }
+
+    /// Writes the encoded integer into `w`.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "i{}e", self.0)
+    }
+
+    /// Returns the length of the encoded integer.
+    pub fn encoded_len(&self) -> usize {
+        self.0.len() + 2
+    }
+}
+
+impl<'a> BList<'a> {
+    /// Writes the encoded list into `w`.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"l")?;
+
+        for item in &self.0 {
+            item.encode_to(w)?;
         }
+
+        w.write_all(b"e")
+    }
+
+    /// Returns the length of the encoded list.
+    pub fn encoded_len(&self) -> usize {
+        self.0.iter().map(Item::encoded_len).sum::<usize>() + 2
+    }
+}
+
+impl<'a> BDictionary<'a> {
+    /// Writes the encoded dictionary into `w`.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        encode_pairs_to(&self.0, w)
+    }
+
+    /// Returns the length of the encoded dictionary.
+    pub fn encoded_len(&self) -> usize {
+        pairs_encoded_len(&self.0)
+    }
+}
+
+impl<'a> BOrderedDictionary<'a> {
+    /// Writes the encoded dictionary into `w`, keeping the order of keys.
+    pub fn encode_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        encode_pairs_to(self.0.iter().map(|(k, v)| (k, v)), w)
+    }
+
+    /// Returns the length of the encoded dictionary.
+    pub fn encoded_len(&self) -> usize {
+        pairs_encoded_len(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+/// Writes encoded dictionary pairs into `w`.
+fn encode_pairs_to<'i, 'a: 'i, I, W>(pairs: I, w: &mut W) -> io::Result<()>
+where
+    I: IntoIterator<Item = (&'i BString<'a>, &'i Item<'a>)>,
+    W: Write,
+{
+    w.write_all(b"d")?;
+
+    for (k, v) in pairs {
+        k.encode_to(w)?;
+        v.encode_to(w)?;
+    }
+
+    w.write_all(b"e")
+}
+
+/// Returns the length of encoded dictionary pairs.
+fn pairs_encoded_len<'i, 'a: 'i, I>(pairs: I) -> usize
+where
+    I: IntoIterator<Item = (&'i BString<'a>, &'i Item<'a>)>,
+{
+    pairs.into_iter().map(|(k, v)| k.encoded_len() + v.encoded_len()).sum::<usize>() + 2
+}
+
+/// Returns the number of decimal digits of `n`.
+fn decimal_len(n: u64) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1)
+}
+
+/// Encodes into a vector of exactly the right capacity.
+fn encode_to_vec(len: usize, encode_to: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+    let mut v = Vec::with_capacity(len);
+
+    encode_to(&mut v).expect("writing to a `Vec` cannot fail");
+
+    v
+}
+
+impl<'a> Bencode for Item<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
     }
 }
 
 impl<'a> Bencode for BString<'a> {
     fn encode(self) -> Vec<u8> {
-        format!("{}:", self.0.len())
-            .into_bytes()
-            .into_iter()
-            .chain(self.0.into_owned().into_iter())
-            .collect()
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl Bencode for BInteger {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
     }
 }
 
-impl<'a> Bencode for BInteger {
+impl Bencode for BBigInteger {
     fn encode(self) -> Vec<u8> {
-        format!("i{}e", self.0).bytes().collect()
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
     }
 }
 
 impl<'a> Bencode for BList<'a> {
     fn encode(self) -> Vec<u8> {
-        std::iter::once(b'l')
-            .chain(self.0.into_iter().flat_map(|i| i.encode()))
-            .chain(std::iter::once(b'e'))
-            .collect()
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
     }
 }
 
 impl<'a> Bencode for BDictionary<'a> {
     fn encode(self) -> Vec<u8> {
-        std::iter::once(b'd')
-            .chain({
-                self.0
-                    .into_iter()
-                    .flat_map(|(k, v)| k.encode().into_iter().chain(v.encode().into_iter()))
-            })
-            .chain(std::iter::once(b'e'))
-            .collect()
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'a> Bencode for BOrderedDictionary<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'r, 'a> Bencode for &'r Item<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'r, 'a> Bencode for &'r BString<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl Bencode for &BInteger {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl Bencode for &BBigInteger {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'r, 'a> Bencode for &'r BList<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'r, 'a> Bencode for &'r BDictionary<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'r, 'a> Bencode for &'r BOrderedDictionary<'a> {
+    fn encode(self) -> Vec<u8> {
+        encode_to_vec(self.encoded_len(), |v| self.encode_to(v))
+    }
+}
+
+impl<'a> From<BOrderedDictionary<'a>> for BDictionary<'a> {
+    /// Sorts the dictionary. For duplicate keys, the last value wins.
+    fn from(d: BOrderedDictionary<'a>) -> Self {
+        BDictionary(d.0.into_iter().collect())
+    }
+}
+
+impl<'a> From<BDictionary<'a>> for BOrderedDictionary<'a> {
+    fn from(d: BDictionary<'a>) -> Self {
+        BOrderedDictionary(d.0.into_iter().collect())
     }
 }
 
@@ -123,6 +747,39 @@ impl<'a> From<i64> for Item<'a> {
     }
 }
 
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> From<$t> for Item<'a> {
+                fn from(i: $t) -> Self {
+                    Self::Integer(BInteger(i64::from(i)))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, u8, u16, u32);
+
+macro_rules! impl_from_wide_integer {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> From<$t> for Item<'a> {
+                /// Creates an [`Item::Integer`] if the value fits into `i64`, or an
+                /// [`Item::BigInteger`] otherwise.
+                fn from(i: $t) -> Self {
+                    match i64::try_from(i) {
+                        Ok(i) => Self::Integer(BInteger(i)),
+                        Err(_) => Self::BigInteger(BBigInteger(i.to_string())),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_wide_integer!(isize, usize, u64, i128, u128);
+
 impl<'a> From<&'a [u8]> for Item<'a> {
     fn from(b: &'a [u8]) -> Self {
         Self::String(BString(Cow::from(b)))
@@ -141,6 +798,59 @@ impl<'a> From<&'a str> for Item<'a> {
     }
 }
 
+impl<'a, const N: usize> From<&'a [u8; N]> for Item<'a> {
+    fn from(b: &'a [u8; N]) -> Self {
+        Self::from(&b[..])
+    }
+}
+
+impl<'a> From<String> for Item<'a> {
+    fn from(s: String) -> Self {
+        Self::String(BString::from(s))
+    }
+}
+
+impl<'a> From<BString<'a>> for Item<'a> {
+    fn from(s: BString<'a>) -> Self {
+        Self::String(s)
+    }
+}
+
+impl<'a> From<BInteger> for Item<'a> {
+    fn from(i: BInteger) -> Self {
+        Self::Integer(i)
+    }
+}
+
+impl<'a> From<BBigInteger> for Item<'a> {
+    /// Creates an [`Item::Integer`] if the value fits into `i64`, or an
+    /// [`Item::BigInteger`] otherwise.
+    fn from(i: BBigInteger) -> Self {
+        match i.to_i64() {
+            Some(i) => Self::Integer(BInteger(i)),
+            None => Self::BigInteger(i),
+        }
+    }
+}
+
+impl<'a> From<BList<'a>> for Item<'a> {
+    fn from(l: BList<'a>) -> Self {
+        Self::List(l)
+    }
+}
+
+impl<'a> From<BDictionary<'a>> for Item<'a> {
+    fn from(d: BDictionary<'a>) -> Self {
+        Self::Dictionary(d)
+    }
+}
+
+impl<'a> From<BOrderedDictionary<'a>> for Item<'a> {
+    fn from(d: BOrderedDictionary<'a>) -> Self {
+        Self::OrderedDictionary(d)
+    }
+}
+
 impl<'a> From<Vec<Item<'a>>> for Item<'a> {
     fn from(v: Vec<Item<'a>>) -> Self {
         Self::List(BList(v))
@@ -165,16 +875,51 @@ impl<'a> From<&'a str> for BString<'a> {
     }
 }
 
+impl<'a> From<&'a [u8]> for BString<'a> {
+    fn from(b: &'a [u8]) -> Self {
+        BString(Cow::from(b))
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for BString<'a> {
+    fn from(b: &'a [u8; N]) -> Self {
+        BString(Cow::from(&b[..]))
+    }
+}
+
+impl<'a> From<Vec<u8>> for BString<'a> {
+    fn from(b: Vec<u8>) -> Self {
+        BString(Cow::Owned(b))
+    }
+}
+
+impl<'a> From<String> for BString<'a> {
+    fn from(s: String) -> Self {
+        BString(Cow::Owned(s.into_bytes()))
+    }
+}
+
+impl<'a> Borrow<[u8]> for BString<'a> {
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl<'a> Display for BString<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(s) = str::from_utf8(&self.0) {
-            write!(f, "{}", s)
-        } else {
-            Debug::fmt(&self.0, f)
+        match self.as_str() {
+            Some(s) => write!(f, "{}", s),
+            None => Debug::fmt(&self.0, f),
         }
     }
 }
 
+impl Display for BBigInteger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 impl<'a> Debug for BString<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "BString(")?;
@@ -184,10 +929,9 @@ impl<'a> Debug for BString<'a> {
             Cow::Owned(_) => write!(f, "owned(")?,
         }
 
-        if let Ok(s) = str::from_utf8(&self.0) {
-            write!(f, "{}", s)?;
-        } else {
-            Debug::fmt(&self.0, f).expect("formatting error");
+        match self.as_str() {
+            Some(s) => write!(f, "{}", s)?,
+            None => Debug::fmt(&self.0, f).expect("formatting error"),
         }
 
         write!(f, "))")
@@ -196,6 +940,7 @@ impl<'a> Debug for BString<'a> {
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
     use std::collections::BTreeMap;
     use std::str::from_utf8_unchecked;
 
@@ -226,6 +971,27 @@ mod tests {
         assert_eq!(expected, actual)
     }
 
+    #[test]
+    fn big_integer() {
+        let big = BBigInteger::new("-170141183460469231731687303715884105728").unwrap();
+
+        assert_eq!(big.to_i128(), Some(i128::MIN));
+        assert_eq!((big.to_i64(), big.to_u64(), big.to_u128()), (None, None, None));
+        assert!(big.is_negative());
+        assert_eq!(big.encode(), b"i-170141183460469231731687303715884105728e");
+
+        for digits in ["", "-", "-0", "00", "01", "+1", "1a", " 1"] {
+            assert_eq!(BBigInteger::new(digits), None, "digits = {:?}", digits);
+        }
+
+        assert_eq!(Item::from(u64::MAX).encode(), b"i18446744073709551615e");
+        assert_eq!(Item::from(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(Item::from(u128::MAX).as_i128(), None);
+        assert_eq!(Item::from(1_u128), Item::Integer(BInteger(1)));
+        assert_eq!(Item::from(BBigInteger::new("-5").unwrap()), Item::Integer(BInteger(-5)));
+        assert!(Item::from(i128::MAX).is_int() && Item::from(i128::MAX).is_big_int());
+    }
+
     #[test]
     fn empty_list() {
         let expected = "le";
@@ -262,6 +1028,67 @@ mod tests {
         assert_eq!(expected, unsafe { from_utf8_unchecked(&actual) })
     }
 
+    #[test]
+    fn ordered_dictionary() {
+        let expected = "d3:fooi1e3:bari2e3:bazi3ee";
+
+        let mut d = BOrderedDictionary::default();
+        d.insert("foo".into(), 1.into());
+        d.insert("bar".into(), 0.into());
+        d.insert("baz".into(), 3.into());
+        assert_eq!(d.insert("bar".into(), 2.into()), Some(0.into()));
+
+        let actual = d.encode();
+
+        assert_eq!(expected, unsafe { from_utf8_unchecked(&actual) })
+    }
+
+    #[test]
+    fn encoded_len() {
+        let items: Vec<Item> = vec![
+            0.into(),
+            9.into(),
+            10.into(),
+            (-1).into(),
+            i64::MIN.into(),
+            i64::MAX.into(),
+            "".into(),
+            "0123456789".into(),
+            vec![vec![].into(), "foo".into()].into(),
+            vec![("bar".into(), 1.into()), ("foo".into(), vec![(-50).into()].into())].into_iter().collect(),
+        ];
+
+        for item in items {
+            assert_eq!(item.encoded_len(), item.clone().encode().len(), "item = {:?}", item);
+        }
+    }
+
+    #[test]
+    fn encode_to() {
+        let item: Item = vec![("foo".into(), vec![34.into(), "bar".into()].into())].into_iter().collect();
+
+        let mut v = vec![];
+        item.encode_to(&mut v).unwrap();
+        item.encode_to(&mut v).unwrap();
+
+        assert_eq!(v, b"d3:fooli34e3:bareed3:fooli34e3:baree");
+    }
+
+    #[test]
+    fn encode_by_reference() {
+        let item: Item = vec![("foo".into(), vec![34.into(), "bar".into()].into())].into_iter().collect();
+
+        let expected = b"d3:fooli34e3:baree";
+
+        assert_eq!((&item).encode(), expected);
+        assert_eq!((&item).encode(), expected);
+
+        let items = [item, 1.into()];
+
+        assert_eq!(items.iter().encode(), b"d3:fooli34e3:bareei1e");
+        assert_eq!(items.len(), 2);
+    }
+
     #[test]
     fn simple_dictionary() {
         let expected = "d3:fooli34e3:bari-50eee";
@@ -273,4 +1100,80 @@ mod tests {
 
         assert_eq!(expected, unsafe { from_utf8_unchecked(&actual) })
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn accessors() {
+        let mut item = Decoder::new(b"d1:ai1e1:b3:foo1:c1:\xff1:dlee").decode_single().unwrap();
+
+        assert_eq!(item["a"].as_int(), Some(1));
+        assert_eq!(item["b"].as_str(), Some("foo"));
+        assert_eq!(item["c"].as_str(), None);
+        assert_eq!(item["c"].as_bytes(), Some(&b"\xff"[..]));
+        assert_eq!(item["d"].as_list(), Some(&BList(vec![])));
+        assert!(item.as_dict().is_some());
+
+        assert!(item["a"].is_int() && !item["a"].is_bytes());
+        assert!(item["b"].is_str() && item["b"].is_bytes());
+        assert!(item["c"].is_bytes() && !item["c"].is_str());
+        assert!(item["d"].is_list() && !item["d"].is_null());
+        assert!(item.is_dict() && !item.is_ordered_dict());
+
+        if let Some(Item::Integer(i)) = item.get_mut("a") {
+            i.0 += 1;
+        }
+
+        *item.get_mut("a").and_then(Item::as_int_mut).unwrap() *= 10;
+        item.get_mut("b").and_then(Item::as_bytes_mut).unwrap().to_mut().push(b'!');
+        item.get_mut("d").and_then(Item::as_list_mut).unwrap().0.push(2.into());
+        item.as_dict_mut().unwrap().0.remove(&b"c"[..]);
+
+        assert_eq!(item.encode(), b"d1:ai20e1:b4:foo!1:dli2eee");
+    }
+
+    #[test]
+    fn index() {
+        let item = Decoder::new(b"d3:fooli1el1:aeee").decode_single().unwrap();
+
+        assert_eq!(item["foo"][1][0].as_str(), Some("a"));
+        assert_eq!(item.get("foo[2]"), None);
+
+        for missing in [&item["bar"], &item[0], &item["foo"][2], &item["foo"][0]["x"], &item["bar"]["x"][1]] {
+            assert!(missing.is_null());
+            assert!(!missing.is_list());
+            assert_eq!(missing.as_list(), None);
+            assert_eq!(missing.get("x"), None);
+        }
+    }
+
+    fn is_owned(item: &Item) -> bool {
+        match item {
+            Item::String(s) => matches!(s.0, Cow::Owned(_)),
+            Item::Integer(_) | Item::BigInteger(_) => true,
+            Item::List(l) => l.0.iter().all(is_owned),
+            Item::Dictionary(d) => d.0.iter().all(|(k, v)| matches!(k.0, Cow::Owned(_)) && is_owned(v)),
+            Item::OrderedDictionary(d) => d.0.iter().all(|(k, v)| matches!(k.0, Cow::Owned(_)) && is_owned(v)),
+        }
+    }
+
+    #[test]
+    fn owned() {
+        let buffer = b"d3:bard1:xl1:yee3:fooli1e3:bazee".to_vec();
+
+        let item = Decoder::new(&buffer).decode_single().unwrap();
+
+        assert!(!is_owned(&item));
+
+        let copy = item.to_owned_item();
+        let owned = item.into_owned();
+
+        drop(buffer);
+
+        assert!(is_owned(&copy) && is_owned(&owned));
+        assert_eq!(copy, owned);
+        assert_eq!(owned.get_str("bar.x[0]"), Some("y"));
+
+        let handle = std::thread::spawn(move || owned.encode());
+
+        assert_eq!(handle.join().unwrap(), b"d3:bard1:xl1:yee3:fooli1e3:bazee");
+    }
+}
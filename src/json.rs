@@ -0,0 +1,574 @@
+use std::fmt::Write;
+use std::str;
+
+use crate::decode::{parse_integer_lenient, Leniency};
+use crate::items::*;
+use crate::util::{hex, parse_hex, write_quoted, Escape, MAX_DEPTH};
+use crate::ErrorKind::*;
+use crate::{DecodeError, ErrorKind};
+
+/// The tag of a byte string that is not valid UTF-8.
+const HEX: &str = "$hex";
+/// The tag of a dictionary written as a list of pairs.
+const DICT: &str = "$dict";
+/// The tag of an ordered dictionary written as a list of pairs.
+const ORDERED: &str = "$ordered";
+
+/// Converts an item into JSON, for reading and for JSON tooling.
+///
+/// Byte strings become JSON strings if they are valid UTF-8, or `{"$hex": "..."}` objects
+/// otherwise. Integers of any size become numbers, lists become arrays and dictionaries
+/// become objects. Dictionary keys that are not valid UTF-8 have invalid sequences replaced
+/// with `U+FFFD`, so use [`to_json_lossless`] when the result has to be converted back.
+///
+/// ```
+/// use yabel::{bencode, to_json};
+///
+/// let item = bencode!({ "name": "foo", "pieces": b"\x00\xff", "length": 7 });
+///
+/// assert_eq!(to_json(&item), r#"{"length":7,"name":"foo","pieces":{"$hex":"00ff"}}"#);
+/// ```
+pub fn to_json(item: &Item) -> String {
+    JsonWriter::new(false).write(item)
+}
+
+/// Converts an item into JSON that [`from_json`] converts back into the very same item.
+///
+/// The output is the same as the one of [`to_json`], except for dictionaries that have keys
+/// which are not valid UTF-8 or start with `$`. Those are written as
+/// `{"$dict": [[key, value], ...]}`, and ordered dictionaries as
+/// `{"$ordered": [[key, value], ...]}`, keys being strings or `{"$hex": "..."}` objects.
+///
+/// ```
+/// use yabel::{bencode, from_json, to_json_lossless};
+///
+/// let item = bencode!({ (&b"\xff"[..]): 1, "$hex": "a" });
+/// let json = to_json_lossless(&item);
+///
+/// assert_eq!(json, r#"{"$dict":[["$hex","a"],[{"$hex":"ff"},1]]}"#);
+/// assert_eq!(from_json(&json).unwrap(), item);
+/// ```
+pub fn to_json_lossless(item: &Item) -> String {
+    JsonWriter::new(true).write(item)
+}
+
+/// Converts JSON into an item.
+///
+/// Strings become byte strings, numbers become integers, arrays become lists and objects
+/// become dictionaries. The `$hex`, `$dict` and `$ordered` objects written by
+/// [`to_json_lossless`] are converted back into what they stand for.
+///
+/// # Errors
+///
+/// Returns an error at the offending byte offset if the input is not valid JSON, or if it
+/// has no bencode representation: numbers with fractions or exponents, `true`, `false`,
+/// `null`, duplicate keys or malformed tagged objects. Arrays and objects nested deeper than
+/// 256 levels are rejected with [`ErrorKind::DepthLimitExceeded`].
+pub fn from_json(json: &str) -> Result<Item<'static>, DecodeError> {
+    let mut parser = JsonParser {
+        bytes: json.as_bytes(),
+        cursor: 0,
+        depth: 0,
+    };
+
+    let item = parser.parse_value()?;
+
+    parser.skip_whitespace();
+
+    match parser.bytes.get(parser.cursor) {
+        None => Ok(item),
+        Some(_) => Err(parser.error(TrailingData, parser.cursor)),
+    }
+}
+
+/// Writes items as JSON.
+struct JsonWriter {
+    out: String,
+    lossless: bool,
+}
+
+impl JsonWriter {
+    fn new(lossless: bool) -> Self {
+        Self {
+            out: String::new(),
+            lossless,
+        }
+    }
+
+    fn write(mut self, item: &Item) -> String {
+        self.write_item(item);
+        self.out
+    }
+
+    fn write_item(&mut self, item: &Item) {
+        match item {
+            Item::String(s) => self.write_bytes(s),
+            Item::Integer(i) => write!(self.out, "{}", i.0).expect("writing into a string does not fail"),
+            Item::BigInteger(i) => self.out.push_str(i.as_str()),
+            Item::List(l) => {
+                self.out.push('[');
+
+                for (i, item) in l.0.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+
+                    self.write_item(item);
+                }
+
+                self.out.push(']');
+            },
+            Item::Dictionary(d) if self.lossless && !d.0.keys().all(is_plain_key) => self.write_pairs(DICT, d.0.iter()),
+            Item::Dictionary(d) => self.write_object(d.0.iter()),
+            Item::OrderedDictionary(d) if self.lossless => self.write_pairs(ORDERED, d.0.iter().map(|(k, v)| (k, v))),
+            Item::OrderedDictionary(d) => self.write_object(d.0.iter().map(|(k, v)| (k, v))),
+        }
+    }
+
+    /// Writes a byte string as a string, or as a `$hex` object if it is not valid UTF-8.
+    fn write_bytes(&mut self, s: &BString) {
+        match s.as_str() {
+            Some(s) => self.write_str(s),
+            None => {
+                self.out.push_str("{\"");
+                self.out.push_str(HEX);
+                self.out.push_str("\":\"");
+                self.out.push_str(&hex(&s.0));
+                self.out.push_str("\"}");
+            },
+        }
+    }
+
+    fn write_object<'i, 'a: 'i>(&mut self, pairs: impl Iterator<Item = (&'i BString<'a>, &'i Item<'a>)>) {
+        self.out.push('{');
+
+        for (i, (k, v)) in pairs.enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+
+            self.write_str(&String::from_utf8_lossy(&k.0));
+            self.out.push(':');
+            self.write_item(v);
+        }
+
+        self.out.push('}');
+    }
+
+    /// Writes dictionary pairs as a tagged list of `[key, value]` lists.
+    fn write_pairs<'i, 'a: 'i>(&mut self, tag: &str, pairs: impl Iterator<Item = (&'i BString<'a>, &'i Item<'a>)>) {
+        self.out.push_str("{\"");
+        self.out.push_str(tag);
+        self.out.push_str("\":[");
+
+        for (i, (k, v)) in pairs.enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+
+            self.out.push('[');
+            self.write_bytes(k);
+            self.out.push(',');
+            self.write_item(v);
+            self.out.push(']');
+        }
+
+        self.out.push_str("]}");
+    }
+
+    /// Writes a quoted and escaped string.
+    fn write_str(&mut self, s: &str) {
+        write_quoted(&mut self.out, s, Escape::Json).expect("writing into a string does not fail");
+    }
+}
+
+/// Returns `true` if the key can be written as an object key without losing information.
+fn is_plain_key(key: &BString) -> bool {
+    key.as_str().is_some_and(|s| !s.starts_with('$'))
+}
+
+/// Parses JSON into items.
+struct JsonParser<'j> {
+    bytes: &'j [u8],
+    cursor: usize,
+    depth: usize,
+}
+
+impl<'j> JsonParser<'j> {
+    fn error(&self, kind: ErrorKind, offset: usize) -> DecodeError {
+        DecodeError::new(kind, offset).with_bytes(self.bytes.get(offset..offset + 1).unwrap_or(&[]))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.cursor) {
+            self.cursor += 1;
+        }
+    }
+
+    /// Consumes `byte` after optional whitespace.
+    fn expect(&mut self, byte: u8) -> Result<(), DecodeError> {
+        self.skip_whitespace();
+
+        match self.bytes.get(self.cursor) {
+            Some(b) if *b == byte => {
+                self.cursor += 1;
+                Ok(())
+            },
+            Some(b) => Err(self.error(UnexpectedByte(*b), self.cursor)),
+            None => Err(self.error(UnexpectedEndOfBuffer, self.cursor)),
+        }
+    }
+
+    /// Consumes `byte` after optional whitespace, if it is the next one.
+    fn consume(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+
+        let found = self.bytes.get(self.cursor) == Some(&byte);
+
+        if found {
+            self.cursor += 1;
+        }
+
+        found
+    }
+
+    fn parse_value(&mut self) -> Result<Item<'static>, DecodeError> {
+        self.skip_whitespace();
+
+        match self.bytes.get(self.cursor) {
+            Some(b'"') => self.parse_string().map(|s| Item::String(BString::from(s))),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b'[') => self.nested(|p| p.parse_array().map(|l| Item::List(BList(l)))),
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b) => Err(self.error(UnexpectedByte(*b), self.cursor)),
+            None => Err(self.error(UnexpectedEndOfBuffer, self.cursor)),
+        }
+    }
+
+    /// Parses an array or an object one level deeper.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Item<'static>, DecodeError>) -> Result<Item<'static>, DecodeError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(DepthLimitExceeded, self.cursor));
+        }
+
+        self.depth += 1;
+
+        let item = parse(self);
+
+        self.depth -= 1;
+
+        item
+    }
+
+    fn parse_number(&mut self) -> Result<Item<'static>, DecodeError> {
+        let start = self.cursor;
+
+        self.cursor += 1;
+
+        while let Some(b'0'..=b'9') = self.bytes.get(self.cursor) {
+            self.cursor += 1;
+        }
+
+        if let Some(b @ (b'.' | b'e' | b'E')) = self.bytes.get(self.cursor) {
+            return Err(self.error(UnexpectedByte(*b), self.cursor));
+        }
+
+        parse_integer_lenient(&self.bytes[start..self.cursor], Leniency::default(), |_| {})
+            .map_err(|kind| DecodeError::new(kind, start).with_bytes(&self.bytes[start..self.cursor]))
+    }
+
+    fn parse_array(&mut self) -> Result<Vec<Item<'static>>, DecodeError> {
+        self.cursor += 1;
+
+        let mut items = vec![];
+
+        if self.consume(b']') {
+            return Ok(items);
+        }
+
+        loop {
+            items.push(self.parse_value()?);
+
+            if self.consume(b']') {
+                return Ok(items);
+            }
+
+            self.expect(b',')?;
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Item<'static>, DecodeError> {
+        let start = self.cursor;
+
+        self.cursor += 1;
+
+        let mut pairs = vec![];
+
+        if !self.consume(b'}') {
+            loop {
+                self.skip_whitespace();
+
+                let offset = self.cursor;
+
+                match self.bytes.get(offset) {
+                    Some(b'"') => {},
+                    Some(b) => return Err(self.error(UnexpectedByte(*b), offset)),
+                    None => return Err(self.error(UnexpectedEndOfBuffer, offset)),
+                }
+
+                let key = BString::from(self.parse_string()?);
+
+                self.expect(b':')?;
+
+                let value = self.parse_value()?;
+
+                pairs.push((offset, key, value));
+
+                if self.consume(b'}') {
+                    break;
+                }
+
+                self.expect(b',')?;
+            }
+        }
+
+        if pairs.len() == 1 {
+            let (offset, key, value) = pairs.pop().expect("there is a single pair");
+
+            match (key.as_str(), value) {
+                (Some(HEX), Item::String(hex)) => {
+                    return parse_hex(&hex.0)
+                        .map(|b| Item::String(BString::from(b)))
+                        .ok_or_else(|| self.error(InvalidData, start));
+                },
+                (Some(DICT), Item::List(l)) => {
+                    let mut dict = BDictionary::default();
+
+                    for (key, value) in self.tagged_pairs(l, start)? {
+                        if dict.0.insert(key, value).is_some() {
+                            return Err(self.error(DuplicateKey, start));
+                        }
+                    }
+
+                    return Ok(Item::Dictionary(dict));
+                },
+                (Some(ORDERED), Item::List(l)) => {
+                    let pairs = self.tagged_pairs(l, start)?;
+
+                    return Ok(Item::OrderedDictionary(BOrderedDictionary(pairs)));
+                },
+                (_, value) => pairs.push((offset, key, value)),
+            }
+        }
+
+        let mut dict = BDictionary::default();
+
+        for (offset, key, value) in pairs {
+            if dict.0.insert(key, value).is_some() {
+                return Err(self.error(DuplicateKey, offset));
+            }
+        }
+
+        Ok(Item::Dictionary(dict))
+    }
+
+    /// Converts the list of a `$dict` or `$ordered` object into dictionary pairs.
+    fn tagged_pairs(&self, list: BList<'static>, start: usize) -> Result<Vec<(BString<'static>, Item<'static>)>, DecodeError> {
+        list.0
+            .into_iter()
+            .map(|pair| match pair {
+                Item::List(BList(pair)) => match <[Item; 2]>::try_from(pair) {
+                    Ok([Item::String(key), value]) => Ok((key, value)),
+                    _ => Err(self.error(InvalidData, start)),
+                },
+                _ => Err(self.error(InvalidData, start)),
+            })
+            .collect()
+    }
+
+    /// Parses a string, the cursor is at the opening quote.
+    fn parse_string(&mut self) -> Result<Vec<u8>, DecodeError> {
+        self.cursor += 1;
+
+        let mut s = vec![];
+
+        loop {
+            let offset = self.cursor;
+
+            let b = *self
+                .bytes
+                .get(offset)
+                .ok_or_else(|| self.error(UnexpectedEndOfBuffer, offset))?;
+
+            self.cursor += 1;
+
+            match b {
+                b'"' => return Ok(s),
+                b'\\' => {
+                    let escape = *self
+                        .bytes
+                        .get(self.cursor)
+                        .ok_or_else(|| self.error(UnexpectedEndOfBuffer, self.cursor))?;
+
+                    self.cursor += 1;
+
+                    match escape {
+                        b'"' | b'\\' | b'/' => s.push(escape),
+                        b'b' => s.push(0x08),
+                        b'f' => s.push(0x0c),
+                        b'n' => s.push(b'\n'),
+                        b'r' => s.push(b'\r'),
+                        b't' => s.push(b'\t'),
+                        b'u' => {
+                            let c = self.parse_unicode_escape(offset)?;
+
+                            s.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        },
+                        b => return Err(self.error(UnexpectedByte(b), self.cursor - 1)),
+                    }
+                },
+                b if b < 0x20 => return Err(self.error(UnexpectedByte(b), offset)),
+                b => s.push(b),
+            }
+        }
+    }
+
+    /// Parses the digits of a `\u` escape, including the low half of a surrogate pair.
+    fn parse_unicode_escape(&mut self, start: usize) -> Result<char, DecodeError> {
+        let high = self.parse_code_unit()?;
+
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.bytes[self.cursor..].starts_with(b"\\u") {
+                    return Err(self.error(InvalidData, start));
+                }
+
+                self.cursor += 2;
+
+                match self.parse_code_unit()? {
+                    low @ 0xdc00..=0xdfff => 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                    _ => return Err(self.error(InvalidData, start)),
+                }
+            },
+            code => code,
+        };
+
+        char::from_u32(code).ok_or_else(|| self.error(InvalidData, start))
+    }
+
+    /// Parses four hex digits.
+    fn parse_code_unit(&mut self) -> Result<u32, DecodeError> {
+        let digits = self
+            .bytes
+            .get(self.cursor..self.cursor + 4)
+            .ok_or_else(|| self.error(UnexpectedEndOfBuffer, self.cursor))?;
+
+        let code = parse_hex(digits)
+            .map(|b| u32::from(b[0]) << 8 | u32::from(b[1]))
+            .ok_or_else(|| self.error(InvalidData, self.cursor))?;
+
+        self.cursor += 4;
+
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bencode, Bencode, Decoder, Settings};
+
+    #[test]
+    fn readable() {
+        let item = bencode!({
+            "announce": "http://tracker/",
+            "list": [1, -2, "\"quoted\"\n", []],
+            "big": Item::from(u64::MAX),
+            "bytes": b"\x01\xfe",
+            (&b"\xff"[..]): {},
+        });
+
+        assert_eq!(
+            to_json(&item),
+            r#"{"announce":"http://tracker/","big":18446744073709551615,"bytes":{"$hex":"01fe"},"list":[1,-2,"\"quoted\"\n",[]],"�":{}}"#
+        );
+    }
+
+    #[test]
+    fn lossless() {
+        let ordered = Decoder::new(b"d1:bi1e1:ai2e2:$x0:e")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        let items = [
+            ordered,
+            bencode!({ "a": { "$hex": "ff" }, "b": [{}, b"\x00\x80", "\u{1}\u{7f}é"] }),
+            bencode!({ (&b"\xc3"[..]): 1, (&b"\xc3\xa9"[..]): 2 }),
+            bencode!([(Item::from(i128::MIN)), 0, ""]),
+        ];
+
+        for item in items {
+            let json = to_json_lossless(&item);
+
+            assert_eq!(from_json(&json).as_ref(), Ok(&item), "json = {}", json);
+        }
+
+        let ordered = Decoder::new(b"d1:bi1e1:ai2ee")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        let json = to_json_lossless(&ordered);
+
+        assert_eq!(json, r#"{"$ordered":[["b",1],["a",2]]}"#);
+        assert_eq!(from_json(&json).unwrap().encode(), b"d1:bi1e1:ai2ee");
+    }
+
+    #[test]
+    fn parsing() {
+        let json = " { \"a\" : [ 1 , \"x\\u00e9\\ud83d\\ude00\\/\" ] , \"b\" : { } , \"$hex\" : 1 } ";
+
+        assert_eq!(from_json(json), Ok(bencode!({ "a": [1, "xé😀/"], "b": {}, "$hex": 1 })));
+        assert_eq!(from_json(r#"{"$hex":"C3A9"}"#), Ok(Item::from("é")));
+        assert_eq!(from_json(r#"{"$hex":1}"#), Ok(bencode!({ "$hex": 1 })));
+    }
+
+    #[test]
+    fn errors() {
+        let cases: [(&str, ErrorKind, usize); 13] = [
+            ("", UnexpectedEndOfBuffer, 0),
+            ("[1,]", UnexpectedByte(b']'), 3),
+            ("1.5", UnexpectedByte(b'.'), 1),
+            ("1e3", UnexpectedByte(b'e'), 1),
+            ("01", LeadingZeros, 0),
+            ("true", UnexpectedByte(b't'), 0),
+            ("[1] 2", TrailingData, 4),
+            ("{\"a\":1,\"a\":2}", DuplicateKey, 7),
+            ("{1:2}", UnexpectedByte(b'1'), 1),
+            ("\"\\x\"", UnexpectedByte(b'x'), 2),
+            ("\"\\ud800\"", InvalidData, 1),
+            ("{\"$hex\":\"abc\"}", InvalidData, 0),
+            ("{\"$dict\":[[1,2]]}", InvalidData, 0),
+        ];
+
+        for (json, kind, offset) in cases {
+            let e = from_json(json).unwrap_err();
+
+            assert_eq!((e.kind(), e.offset()), (kind, offset), "json = {:?}", json);
+        }
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(from_json(&nested(MAX_DEPTH)).is_ok());
+
+        let e = from_json(&"[{\"a\":".repeat(100_000)).unwrap_err();
+
+        assert_eq!((e.kind(), e.offset()), (DepthLimitExceeded, 3 * MAX_DEPTH));
+    }
+}
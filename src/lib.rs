@@ -1,11 +1,41 @@
 //! Yet another bencode library.
 
+mod canonical;
+#[cfg(feature = "serde")]
+mod de;
 mod decode;
 mod encode;
 mod error;
+mod frame;
+mod hash;
 mod items;
+mod json;
+mod macros;
+mod magnet;
+mod parser;
+mod path;
+mod reader;
+#[cfg(feature = "serde")]
+mod ser;
+mod stream;
+mod text;
+mod torrent;
+mod util;
 
+pub use canonical::*;
+#[cfg(feature = "serde")]
+pub use de::*;
 pub use decode::*;
 pub use encode::*;
 pub use error::*;
-pub use items::*;
\ No newline at end of file
+pub use hash::*;
+pub use items::*;
+pub use json::*;
+pub use magnet::*;
+pub use parser::*;
+pub use path::*;
+#[cfg(feature = "serde")]
+pub use ser::*;
+pub use stream::*;
+pub use text::*;
+pub use torrent::*;
\ No newline at end of file
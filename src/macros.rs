@@ -0,0 +1,126 @@
+/// Constructs an [`Item`](crate::Item) from a JSON-like literal.
+///
+/// Lists are written in square brackets, dictionaries in curly braces. Dictionary keys are
+/// string or byte string literals, or any expression convertible into a
+/// [`BString`](crate::BString) wrapped in parentheses. Any other value is an expression
+/// convertible into an [`Item`](crate::Item): integers, strings, byte strings or other items.
+///
+/// Keys are not checked for duplicates, if a key appears more than once the last value wins.
+///
+/// ```
+/// use yabel::{bencode, Bencode};
+///
+/// let id = b"abcdefghij0123456789";
+///
+/// let query = bencode!({ "t": "aa", "y": "q", "q": "ping", "a": { "id": id } });
+///
+/// assert_eq!(query.encode(), b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe");
+///
+/// let list = bencode!([1, -2 * 3, b"\xff", ["nested"], {}]);
+///
+/// assert_eq!(list.encode(), b"li1ei-6e1:\xffl6:nestededee");
+/// ```
+#[macro_export]
+macro_rules! bencode {
+    // Munches list elements, one comma separated expression at a time.
+    (@list [$($elems:expr,)*] []) => {
+        vec![$($elems,)*]
+    };
+    (@list [$($elems:expr,)*] [$($value:tt)+]) => {
+        vec![$($elems,)* $crate::bencode!($($value)+),]
+    };
+    (@list [$($elems:expr,)*] [$($value:tt)+] , $($rest:tt)*) => {
+        $crate::bencode!(@list [$($elems,)* $crate::bencode!($($value)+),] [] $($rest)*)
+    };
+    (@list [$($elems:expr,)*] [$($value:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::bencode!(@list [$($elems,)*] [$($value)* $next] $($rest)*)
+    };
+
+    // Munches dictionary entries, a key first and then its value.
+    (@dict [$($keys:expr => $values:expr,)*]) => {
+        [$(($crate::BString::from($keys), $values),)*]
+            .into_iter()
+            .collect::<::std::collections::BTreeMap<_, _>>()
+    };
+    (@dict [$($entries:tt)*] $key:tt : $($rest:tt)*) => {
+        $crate::bencode!(@value [$($entries)*] ($key) [] $($rest)*)
+    };
+    (@value [$($entries:tt)*] ($key:tt) [$($value:tt)+]) => {
+        $crate::bencode!(@dict [$($entries)* $key => $crate::bencode!($($value)+),])
+    };
+    (@value [$($entries:tt)*] ($key:tt) [$($value:tt)+] , $($rest:tt)*) => {
+        $crate::bencode!(@dict [$($entries)* $key => $crate::bencode!($($value)+),] $($rest)*)
+    };
+    (@value [$($entries:tt)*] ($key:tt) [$($value:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::bencode!(@value [$($entries)*] ($key) [$($value)* $next] $($rest)*)
+    };
+
+    ([$($tt:tt)*]) => {
+        $crate::Item::List($crate::BList($crate::bencode!(@list [] [] $($tt)*)))
+    };
+    ({$($tt:tt)*}) => {
+        $crate::Item::Dictionary($crate::BDictionary($crate::bencode!(@dict [] $($tt)*)))
+    };
+    ($value:expr) => {
+        $crate::Item::from($value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::*;
+
+    #[test]
+    fn scalars() {
+        assert_eq!(bencode!(42), Item::Integer(BInteger(42)));
+        assert_eq!(bencode!(-1 - 1), Item::Integer(BInteger(-2)));
+        assert_eq!(bencode!("foo"), Item::from("foo"));
+        assert_eq!(bencode!(b"\x00\x01"), Item::from(&b"\x00\x01"[..]));
+        assert_eq!(bencode!(String::from("bar")), Item::from("bar"));
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(bencode!([]), Item::List(BList(vec![])));
+        assert_eq!(bencode!([1, "a",]), vec![1.into(), "a".into()].into());
+        assert_eq!(bencode!([[[]]]), vec![vec![vec![].into()].into()].into());
+
+        // unsuffixed literals are still inferred as `i32` and become plain integers
+        assert_eq!(bencode!([1, -1]), Item::List(BList(vec![Item::Integer(BInteger(1)), Item::Integer(BInteger(-1))])));
+    }
+
+    #[test]
+    fn dictionaries() {
+        let id = [7u8; 20];
+        let port: u16 = 6881;
+        let key = String::from("dynamic");
+
+        let actual = bencode!({
+            "t": "aa",
+            b"y": "q",
+            "a": { "id": &id, "port": port, "list": [1, { }] },
+            (key.as_str()): Item::from(-5),
+        });
+
+        let mut a = BTreeMap::new();
+        a.insert("id".into(), Item::from(&id[..]));
+        a.insert("port".into(), 6881.into());
+        a.insert("list".into(), vec![1.into(), BTreeMap::new().into()].into());
+
+        let mut expected = BTreeMap::new();
+        expected.insert("t".into(), "aa".into());
+        expected.insert("y".into(), "q".into());
+        expected.insert("a".into(), a.into());
+        expected.insert("dynamic".into(), (-5).into());
+
+        assert_eq!(actual, Item::from(expected));
+        assert_eq!(bencode!({}), Item::Dictionary(BDictionary::default()));
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        assert_eq!(bencode!({ "a": 1, "b": 2, "a": 3 }), bencode!({ "a": 3, "b": 2 }));
+    }
+}
@@ -0,0 +1,433 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hash::raw_info;
+use crate::util::{hex, parse_hex};
+use crate::{sha1, sha256, MagnetError, Metainfo, TorrentError};
+
+/// The RFC 4648 base32 alphabet.
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The multihash prefix of a SHA-256 hash: the function code `0x12` and the length `0x20`.
+const SHA256_MULTIHASH: &str = "1220";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// A magnet link, see BEP 9.
+///
+/// The link is formatted with [`Display`](fmt::Display), with a hex v1 info-hash, or with
+/// [`Magnet::to_base32_string`]. It is parsed with [`Magnet::parse`] or [`str::parse`].
+///
+/// ```
+/// use yabel::Magnet;
+///
+/// let magnet: Magnet = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056\
+///                       &dn=Cosmos+Laundromat&tr=udp%3A%2F%2Fexplodie.org%3A6969"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(magnet.name.as_deref(), Some("Cosmos Laundromat"));
+/// assert_eq!(magnet.trackers, ["udp://explodie.org:6969"]);
+///
+/// assert_eq!(
+///     magnet.to_string(),
+///     "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056\
+///      &dn=Cosmos%20Laundromat&tr=udp%3A%2F%2Fexplodie.org%3A6969"
+/// );
+/// ```
+pub struct Magnet {
+    /// The BitTorrent v1 info-hash, `xt=urn:btih:`.
+    pub info_hash_v1: Option<[u8; 20]>,
+    /// The BitTorrent v2 info-hash, `xt=urn:btmh:`.
+    pub info_hash_v2: Option<[u8; 32]>,
+    /// The display name, `dn`.
+    pub name: Option<String>,
+    /// Tracker URLs, `tr`.
+    pub trackers: Vec<String>,
+    /// Web seed URLs, `ws`.
+    pub web_seeds: Vec<String>,
+    /// Peer addresses, `x.pe`, e.g. `10.0.0.1:6881`.
+    pub peers: Vec<String>,
+}
+
+impl Magnet {
+    /// Constructs a magnet link for the contents of a `.torrent` file.
+    ///
+    /// The link has the v1 info-hash, the v2 one as well if the torrent is a hybrid one
+    /// (`meta version` 2), the name, the trackers of `announce` and `announce-list`, and
+    /// the web seeds of `url-list`. The input is decoded with
+    /// [`Settings::Lenient`](crate::Settings::Lenient), and the hashes are computed from the
+    /// exact bytes of the `info` dictionary, see [`info_hash_v1`](crate::info_hash_v1), so
+    /// they are right for non-canonical torrents as well.
+    ///
+    /// Only torrents with the v1 keys of the `info` dictionary are supported, v2-only ones are
+    /// rejected just as [`Metainfo`] rejects them.
+    ///
+    /// # Errors
+    ///
+    /// See [`TorrentError`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TorrentError> {
+        let (item, info, _) = raw_info(bytes)?;
+        let metainfo = Metainfo::from_item(item)?;
+
+        Ok(Self {
+            info_hash_v1: Some(sha1(info)),
+            info_hash_v2: is_meta_version_2(&metainfo).then(|| sha256(info)),
+            ..Self::without_hashes(&metainfo)
+        })
+    }
+
+    /// Constructs a magnet link for a torrent, see [`Magnet::from_bytes`].
+    ///
+    /// The hashes are computed from the encoded `info` dictionary, see
+    /// [`Info::hash_v1`](crate::Info::hash_v1). They are the hashes of the original file only
+    /// if it was canonical, so use [`Magnet::from_bytes`] for torrents that were decoded
+    /// leniently or with [`Settings::PreserveDictionaryOrder`](crate::Settings::PreserveDictionaryOrder).
+    pub fn from_metainfo(metainfo: &Metainfo) -> Self {
+        Self {
+            info_hash_v1: Some(metainfo.info.hash_v1()),
+            info_hash_v2: is_meta_version_2(metainfo).then(|| metainfo.info.hash_v2()),
+            ..Self::without_hashes(metainfo)
+        }
+    }
+
+    /// Constructs a magnet link for a torrent, leaving out the info-hashes.
+    fn without_hashes(metainfo: &Metainfo) -> Self {
+        let mut trackers: Vec<String> = vec![];
+
+        for tracker in metainfo.announce.iter().chain(metainfo.announce_list.iter().flatten()) {
+            if !trackers.contains(tracker) {
+                trackers.push(tracker.clone());
+            }
+        }
+
+        // a single web seed may be a string rather than a list
+        let web_seeds = match metainfo.extra.get("url-list") {
+            Some(item) => match item.as_list() {
+                Some(l) => l.0.iter().filter_map(|i| i.as_str()).map(str::to_owned).collect(),
+                None => item.as_str().map(str::to_owned).into_iter().collect(),
+            },
+            None => vec![],
+        };
+
+        Self {
+            info_hash_v1: None,
+            info_hash_v2: None,
+            name: Some(metainfo.info.name.clone()),
+            trackers,
+            web_seeds,
+            peers: vec![],
+        }
+    }
+
+    /// Parses a magnet link.
+    ///
+    /// Parameter values are percent-decoded, with `+` standing for a space. The v1
+    /// info-hash may be either hex or base32 encoded. Indexed parameters, such as `tr.1`,
+    /// are accepted, and unknown parameters are ignored.
+    ///
+    /// # Errors
+    ///
+    /// See [`MagnetError`].
+    pub fn parse(uri: &str) -> Result<Self, MagnetError> {
+        let query = match uri.get(..8) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("magnet:?") => &uri[8..],
+            _ => return Err(MagnetError::NotMagnet),
+        };
+
+        let mut magnet = Magnet::default();
+
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let invalid = || MagnetError::InvalidParameter(param.to_owned());
+
+            let (key, value) = param.split_once('=').ok_or_else(invalid)?;
+            let value = percent_decode(value).ok_or_else(invalid)?;
+
+            let key = match key.rsplit_once('.') {
+                Some((key, index)) if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) => key,
+                _ => key,
+            };
+
+            match key {
+                "xt" => magnet.parse_exact_topic(&value)?,
+                "dn" => magnet.name = Some(value),
+                "tr" => magnet.trackers.push(value),
+                "ws" => magnet.web_seeds.push(value),
+                "x.pe" => magnet.peers.push(value),
+                _ => {},
+            }
+        }
+
+        if magnet.info_hash_v1.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(MagnetError::MissingInfoHash);
+        }
+
+        Ok(magnet)
+    }
+
+    /// Parses an `xt` value, ignoring other kinds of URNs.
+    fn parse_exact_topic(&mut self, xt: &str) -> Result<(), MagnetError> {
+        let invalid = || MagnetError::InvalidInfoHash(xt.to_owned());
+
+        if let Some(hash) = xt.strip_prefix("urn:btih:") {
+            let bytes = match hash.len() {
+                40 => parse_hex(hash.as_bytes()),
+                32 => base32_decode(hash),
+                _ => None,
+            };
+
+            self.info_hash_v1 = Some(bytes.and_then(|b| b.try_into().ok()).ok_or_else(invalid)?);
+        } else if let Some(hash) = xt.strip_prefix("urn:btmh:") {
+            let bytes = hash
+                .strip_prefix(SHA256_MULTIHASH)
+                .and_then(|h| parse_hex(h.as_bytes()));
+
+            self.info_hash_v2 = Some(bytes.and_then(|b| b.try_into().ok()).ok_or_else(invalid)?);
+        }
+
+        Ok(())
+    }
+
+    /// Formats the link with a base32 v1 info-hash.
+    pub fn to_base32_string(&self) -> String {
+        self.format(true)
+    }
+
+    fn format(&self, base32: bool) -> String {
+        let mut params = vec![];
+
+        if let Some(hash) = &self.info_hash_v1 {
+            let hash = if base32 { base32_encode(hash) } else { hex(hash) };
+
+            params.push(format!("xt=urn:btih:{}", hash));
+        }
+
+        if let Some(hash) = &self.info_hash_v2 {
+            params.push(format!("xt=urn:btmh:{}{}", SHA256_MULTIHASH, hex(hash)));
+        }
+
+        let values = [("dn", self.name.as_slice()), ("tr", &self.trackers), ("ws", &self.web_seeds), ("x.pe", &self.peers)];
+
+        for (key, values) in values {
+            params.extend(values.iter().map(|v| format!("{}={}", key, percent_encode(v))));
+        }
+
+        format!("magnet:?{}", params.join("&"))
+    }
+}
+
+/// Returns `true` if the torrent has a v2 info-hash, see BEP 52.
+///
+/// [`Metainfo`] requires the v1 keys of the `info` dictionary, so such a torrent is always a
+/// hybrid one.
+fn is_meta_version_2(metainfo: &Metainfo) -> bool {
+    metainfo.info.extra.get_int("meta version") == Some(2)
+}
+
+impl fmt::Display for Magnet {
+    /// Formats the link with a hex v1 info-hash.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(false))
+    }
+}
+
+impl FromStr for Magnet {
+    type Err = MagnetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(char::from(b)),
+            b => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+
+    encoded
+}
+
+/// Percent-decodes a parameter value, `+` stands for a space.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => decoded.push(parse_hex(&[bytes.next()?, bytes.next()?])?[0]),
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// Encodes bytes as unpadded base32.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0_u16, 0);
+
+    for b in bytes {
+        buffer = buffer << 8 | u16::from(*b);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(char::from(BASE32[usize::from(buffer >> bits & 0x1f)]));
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(char::from(BASE32[usize::from(buffer << (5 - bits) & 0x1f)]));
+    }
+
+    encoded
+}
+
+/// Decodes base32 of either case, either padded to a multiple of 8 characters or unpadded.
+///
+/// Trailing bits that do not make up a byte must be zero, so that every string of bytes
+/// has exactly one encoding.
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let unpadded = s.trim_end_matches('=');
+    let padding = s.len() - unpadded.len();
+
+    if padding != 0 && padding != (8 - unpadded.len() % 8) % 8 {
+        return None;
+    }
+
+    let mut decoded = vec![];
+    let (mut buffer, mut bits) = (0_u16, 0);
+
+    for c in unpadded.bytes() {
+        let value = BASE32.iter().position(|b| *b == c.to_ascii_uppercase())?;
+
+        buffer = buffer << 5 | value as u16;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+
+    if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bencode, info_hash_v1, info_hash_v2, Decoder, Settings};
+
+    #[test]
+    fn base32() {
+        for (bytes, encoded) in [(&b""[..], ""), (b"f", "MY"), (b"fo", "MZXQ"), (b"foo", "MZXW6"), (b"foobar", "MZXW6YTBOI")] {
+            assert_eq!(base32_encode(bytes), encoded);
+            assert_eq!(base32_decode(encoded).as_deref(), Some(bytes));
+        }
+
+        assert_eq!(base32_decode("mzxw6ytboi======").as_deref(), Some(&b"foobar"[..]));
+        assert_eq!(base32_decode("MY======").as_deref(), Some(&b"f"[..]));
+        assert_eq!(base32_decode("MZXW6===").as_deref(), Some(&b"foo"[..]));
+
+        for invalid in ["MZ1", "MZXW6YTBOJ", "MZXW6YTBO", "MY=", "MZXW6==", "MZXW6YTBOI=", "MZXW6YTBOI========"] {
+            assert_eq!(base32_decode(invalid), None, "s = {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(percent_encode("udp://a.b:80/x?y=1&z"), "udp%3A%2F%2Fa.b%3A80%2Fx%3Fy%3D1%26z");
+        assert_eq!(percent_encode("é ~"), "%C3%A9%20~");
+        assert_eq!(percent_decode("%c3%A9+%20~").as_deref(), Some("é  ~"));
+
+        for s in ["%", "%4", "%zz", "%ff"] {
+            assert_eq!(percent_decode(s), None, "s = {:?}", s);
+        }
+    }
+
+    #[test]
+    fn from_metainfo() {
+        let item = bencode!({
+            "announce": "http://a/",
+            "announce-list": [["http://a/", "http://b/"], ["udp://c:80"]],
+            "url-list": "http://seed/",
+            "info": { "name": "a b", "length": 1, "piece length": 1, "pieces": "", "meta version": 2 },
+        });
+
+        let metainfo = Metainfo::from_item(item).unwrap();
+        let magnet = Magnet::from_metainfo(&metainfo);
+
+        assert_eq!(magnet.info_hash_v1, Some(metainfo.info.hash_v1()));
+        assert_eq!(magnet.info_hash_v2, Some(metainfo.info.hash_v2()));
+        assert_eq!(magnet.trackers, ["http://a/", "http://b/", "udp://c:80"]);
+        assert_eq!(magnet.web_seeds, ["http://seed/"]);
+
+        let (v1, v2) = (hex(&metainfo.info.hash_v1()), hex(&metainfo.info.hash_v2()));
+        let tail = "&dn=a%20b&tr=http%3A%2F%2Fa%2F&tr=http%3A%2F%2Fb%2F&tr=udp%3A%2F%2Fc%3A80&ws=http%3A%2F%2Fseed%2F";
+
+        assert_eq!(magnet.to_string(), format!("magnet:?xt=urn:btih:{}&xt=urn:btmh:1220{}{}", v1, v2, tail));
+
+        for uri in [magnet.to_string(), magnet.to_base32_string()] {
+            assert_eq!(Magnet::parse(&uri), Ok(magnet.clone()));
+        }
+    }
+
+    #[test]
+    fn non_canonical_torrent() {
+        // unsorted `info` keys and a leading zero
+        let bytes = b"d4:infod4:name1:a6:lengthi01e12:piece lengthi1e6:pieces0:12:meta versioni2eee";
+
+        let magnet = Magnet::from_bytes(bytes).unwrap();
+
+        assert_eq!(magnet.info_hash_v1, Some(info_hash_v1(bytes).unwrap().hash));
+        assert_eq!(magnet.info_hash_v2, Some(info_hash_v2(bytes).unwrap().hash));
+        assert_eq!(magnet.name.as_deref(), Some("a"));
+
+        let item = Decoder::new(bytes).setting(Settings::Lenient).decode_single().unwrap();
+
+        assert_ne!(Magnet::from_metainfo(&Metainfo::from_item(item).unwrap()).info_hash_v1, magnet.info_hash_v1);
+
+        assert!(matches!(Magnet::from_bytes(b"d4:info"), Err(TorrentError::Decode(_))));
+    }
+
+    #[test]
+    fn parsing() {
+        let magnet = Magnet::parse(
+            "MAGNET:?xt=urn:btih:ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW&tr.1=http%3A%2F%2Fa%2F&tr.2=http://b/\
+             &x.pe=10.0.0.1:6881&x.pe.1=[::1]:6881&tr.=http://c/&xl=1024&&xt=urn:sha1:abc",
+        )
+        .unwrap();
+
+        assert_eq!(hex(&magnet.info_hash_v1.unwrap()), "c9e15763f722f23e98a29decdfae341b98d53056");
+        assert_eq!(magnet.trackers, ["http://a/", "http://b/"]);
+        assert_eq!(magnet.peers, ["10.0.0.1:6881", "[::1]:6881"]);
+        assert_eq!((magnet.info_hash_v2, magnet.name), (None, None));
+    }
+
+    #[test]
+    fn errors() {
+        use MagnetError::*;
+
+        let v1 = "xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056";
+
+        assert_eq!(Magnet::parse("http://a/"), Err(NotMagnet));
+        assert_eq!(Magnet::parse("magnet:"), Err(NotMagnet));
+        assert_eq!(Magnet::parse("magnet:?dn=a"), Err(MissingInfoHash));
+        assert_eq!(Magnet::parse(&format!("magnet:?{}&dn", v1)), Err(InvalidParameter("dn".into())));
+        assert_eq!(Magnet::parse(&format!("magnet:?{}&dn=%ff", v1)), Err(InvalidParameter("dn=%ff".into())));
+        assert_eq!(Magnet::parse("magnet:?xt=urn:btih:c9e1"), Err(InvalidInfoHash("urn:btih:c9e1".into())));
+        assert_eq!(Magnet::parse("magnet:?xt=urn:btmh:1114ab"), Err(InvalidInfoHash("urn:btmh:1114ab".into())));
+        assert_eq!("magnet:?".parse::<Magnet>(), Err(MissingInfoHash));
+    }
+}
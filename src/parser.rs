@@ -0,0 +1,454 @@
+use std::collections::BTreeSet;
+
+use crate::decode::{exceeds, Options};
+use crate::reader::Reader;
+use crate::ErrorKind::*;
+use crate::{DecodeError, DuplicateKeyPolicy, ErrorKind, PathSegment, Settings};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The token type.
+pub enum Token<'a> {
+    /// The beginning of a list.
+    ListStart,
+    /// The beginning of a dictionary.
+    DictStart,
+    /// A dictionary key.
+    Key(&'a [u8]),
+    /// A byte string.
+    Bytes(&'a [u8]),
+    /// An integer.
+    Int(i64),
+    /// An integer out of the `i64` range, as written in the input.
+    ///
+    /// See [`BBigInteger`](crate::BBigInteger).
+    BigInt(&'a [u8]),
+    /// The end of a list or a dictionary.
+    End,
+}
+
+/// A list or a dictionary being parsed.
+#[derive(Debug)]
+enum Frame<'a> {
+    List {
+        index: usize,
+    },
+    Dictionary {
+        key: Option<&'a [u8]>,
+        expects_value: bool,
+        keys: BTreeSet<&'a [u8]>,
+    },
+}
+
+/// Pull parser, a low level alternative to [`Decoder`](crate::Decoder).
+///
+/// Instead of building items, the parser yields a flat sequence of [`Token`]s, borrowed from
+/// the input. It follows the same rules as the decoder does and reports the same errors, but
+/// never allocates for the parsed data. Duplicate keys of unsorted dictionaries are rejected
+/// unless a [`DuplicateKeyPolicy`] other than [`DuplicateKeyPolicy::Reject`] is set, in which
+/// case all of them are yielded. Lenient settings are supported as well, but the tolerated
+/// deviations are not recorded.
+///
+/// Once an error is returned, the parser yields nothing.
+pub struct Parser<'a> {
+    reader: Reader<'a>,
+    stack: Vec<Frame<'a>>,
+    done: bool,
+}
+
+impl<'a> Parser<'a> {
+    /// Constructs a new `Parser` with specified byte buffer.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            reader: Reader::new(bytes, Options::default()),
+            stack: vec![],
+            done: false,
+        }
+    }
+
+    /// Applies a setting for the current parser.
+    ///
+    /// See [`Settings`] enum for a full list.
+    pub fn setting(self, setting: Settings) -> Self {
+        let mut s = self;
+
+        s.reader.options.apply(setting);
+
+        s
+    }
+
+    /// Returns the offset of the next unparsed byte.
+    pub fn offset(&self) -> usize {
+        self.reader.cursor
+    }
+
+    /// Returns the number of lists and dictionaries the parser is currently in.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Parses the next token.
+    ///
+    /// Returns `Ok(None)` when the input is exhausted.
+    pub fn next_token(&mut self) -> Result<Option<Token<'a>>, DecodeError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let result = self.parse_token();
+
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+
+        result
+    }
+
+    /// Skips the next value, including all of its nested items.
+    ///
+    /// If a dictionary key is expected, the key is skipped together with its value. If there
+    /// are no more values in the current list or dictionary, nothing is skipped.
+    pub fn skip_value(&mut self) -> Result<(), DecodeError> {
+        if self.at_end() {
+            return Ok(());
+        }
+
+        let mut depth = 0_usize;
+
+        while let Some(token) = self.next_token()? {
+            match token {
+                Token::ListStart | Token::DictStart => depth += 1,
+                Token::End => depth -= 1,
+                Token::Key(_) => continue,
+                Token::Bytes(_) | Token::Int(_) | Token::BigInt(_) => {},
+            }
+
+            if depth == 0 {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the next token ends the current list or dictionary.
+    fn at_end(&self) -> bool {
+        let expects_item = match self.stack.last() {
+            Some(Frame::Dictionary { expects_value, .. }) => !expects_value,
+            Some(Frame::List { .. }) => true,
+            None => false,
+        };
+
+        let whitespace = self.reader.options.leniency.whitespace;
+
+        let next = self.reader.bytes[self.reader.cursor..]
+            .iter()
+            .find(|b| !(whitespace && b.is_ascii_whitespace()));
+
+        expects_item && next == Some(&b'e')
+    }
+
+    /// Parses the next token, see [`Parser::next_token`].
+    fn parse_token(&mut self) -> Result<Option<Token<'a>>, DecodeError> {
+        if self.reader.cursor == 0 && self.stack.is_empty() {
+            if let Some(limit) = self.reader.options.limits.bytes.filter(|limit| self.reader.bytes.len() > *limit) {
+                return Err(self.error(ByteLimitExceeded, limit, &[]));
+            }
+        }
+
+        if self.reader.options.leniency.whitespace {
+            while self.reader.bytes.get(self.reader.cursor).is_some_and(u8::is_ascii_whitespace) {
+                self.reader.cursor += 1;
+            }
+        }
+
+        let plus_sign = self.reader.options.leniency.plus_sign;
+
+        let byte = match self.reader.bytes.get(self.reader.cursor) {
+            Some(byte) => *byte,
+            None if self.stack.is_empty() => return Ok(None),
+            None => {
+                let path = self.path(false);
+                return Err(DecodeError::new(UnexpectedEndOfBuffer, self.reader.bytes.len()).with_path(path));
+            },
+        };
+
+        let start = self.reader.cursor;
+
+        match self.stack.last() {
+            Some(Frame::List { .. }) | Some(Frame::Dictionary { expects_value: false, .. }) if byte == b'e' => {
+                self.reader.cursor += 1;
+                self.stack.pop();
+                self.value_done();
+
+                return Ok(Some(Token::End));
+            },
+            Some(Frame::Dictionary { expects_value: false, .. }) => {
+                if !(byte.is_ascii_digit() || byte == b'+' && plus_sign) {
+                    return Err(self.error(InvalidDictionaryKey, start, &[byte]));
+                }
+
+                self.count_item()?;
+
+                let key = self.read_string()?;
+
+                self.check_key(start, key)?;
+
+                return Ok(Some(Token::Key(key)));
+            },
+            _ => {},
+        }
+
+        self.count_item()?;
+
+        let token = match byte {
+            b'0'..=b'9' => Token::Bytes(self.read_string()?),
+            b'+' if plus_sign => Token::Bytes(self.read_string()?),
+            b'i' => {
+                let (i, digits) = self.reader.read_integer(|_| {}).map_err(|e| e.with_path(self.path(true)))?;
+
+                match i.as_int() {
+                    Some(i) => Token::Int(i),
+                    None => Token::BigInt(digits),
+                }
+            },
+            b'l' | b'd' => {
+                if exceeds(self.stack.len() + 1, self.reader.options.limits.depth) {
+                    return Err(self.error(DepthLimitExceeded, start, &[byte]));
+                }
+
+                self.reader.cursor += 1;
+
+                return Ok(Some(if byte == b'l' {
+                    self.stack.push(Frame::List { index: 0 });
+                    Token::ListStart
+                } else {
+                    self.stack.push(Frame::Dictionary {
+                        key: None,
+                        expects_value: false,
+                        keys: BTreeSet::new(),
+                    });
+                    Token::DictStart
+                }));
+            },
+            b => return Err(self.error(UnexpectedByte(b), start, &[b])),
+        };
+
+        self.value_done();
+
+        Ok(Some(token))
+    }
+
+    /// Checks a dictionary key against the previous keys and stores it.
+    fn check_key(&mut self, start: usize, key: &'a [u8]) -> Result<(), DecodeError> {
+        let allow_unsorted = self.reader.options.allow_unsorted_dictionaries;
+        let reject_duplicates = self.reader.options.duplicate_keys == DuplicateKeyPolicy::Reject;
+
+        let kind = match self.stack.last() {
+            Some(Frame::Dictionary { key: last, keys, .. }) => match *last {
+                Some(k) if !allow_unsorted && k > key => Some(UnsortedDictionary),
+                Some(k) if !allow_unsorted && k == key => Some(DuplicateKey),
+                _ if allow_unsorted && reject_duplicates && keys.contains(key) => Some(DuplicateKey),
+                _ => None,
+            },
+            _ => unreachable!("keys are parsed only in dictionaries"),
+        };
+
+        if let Some(kind) = kind {
+            return Err(self.error(kind, start, key));
+        }
+
+        if let Some(Frame::Dictionary {
+            key: last,
+            expects_value,
+            keys,
+        }) = self.stack.last_mut()
+        {
+            if allow_unsorted && reject_duplicates {
+                keys.insert(key);
+            }
+
+            *last = Some(key);
+            *expects_value = true;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the current list or dictionary after one of its values was parsed.
+    fn value_done(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::List { index }) => *index += 1,
+            Some(Frame::Dictionary { expects_value, .. }) => *expects_value = false,
+            None => {},
+        }
+    }
+
+    /// Counts an item starting at the cursor position against the item limit.
+    fn count_item(&mut self) -> Result<(), DecodeError> {
+        self.reader.count_item().map_err(|e| e.with_path(self.path(true)))
+    }
+
+    /// Reads a string and returns its bytes.
+    fn read_string(&mut self) -> Result<&'a [u8], DecodeError> {
+        self.reader.read_string(|_| {}).map_err(|e| e.with_path(self.path(true)))
+    }
+
+    /// Constructs an error for an item that is being parsed.
+    fn error(&self, kind: ErrorKind, offset: usize, bytes: &[u8]) -> DecodeError {
+        DecodeError::new(kind, offset).with_path(self.path(true)).with_bytes(bytes)
+    }
+
+    /// Returns the path to the current item, or to the current list or dictionary if `item`
+    /// is `false`.
+    fn path(&self, item: bool) -> Vec<PathSegment> {
+        let frames = if item {
+            &self.stack[..]
+        } else {
+            &self.stack[..self.stack.len().saturating_sub(1)]
+        };
+
+        frames
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::List { index } => Some(PathSegment::Index(*index)),
+                Frame::Dictionary {
+                    key: Some(key),
+                    expects_value: true,
+                    ..
+                } => Some(PathSegment::Key(key.to_vec())),
+                Frame::Dictionary { .. } => None,
+            })
+            .collect()
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<Token<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Token::*;
+    use crate::ErrorKind::*;
+    use crate::{Decoder, DuplicateKeyPolicy, Parser, Settings};
+
+    #[test]
+    fn tokens() {
+        let input = b"d3:bar4:spam3:fooli42ei-7eee4:spam";
+
+        let tokens: Result<Vec<_>, _> = Parser::new(&input[..]).collect();
+
+        assert_eq!(
+            tokens.unwrap(),
+            [
+                DictStart,
+                Key(b"bar"),
+                Bytes(b"spam"),
+                Key(b"foo"),
+                ListStart,
+                Int(42),
+                Int(-7),
+                End,
+                End,
+                Bytes(b"spam")
+            ]
+        );
+    }
+
+    #[test]
+    fn big_integer() {
+        let tokens: Result<Vec<_>, _> = Parser::new(&b"li9223372036854775807ei9223372036854775808ee"[..]).collect();
+
+        assert_eq!(tokens, Ok(vec![ListStart, Int(i64::MAX), BigInt(b"9223372036854775808"), End]));
+    }
+
+    #[test]
+    fn skip_value() {
+        let input = b"d1:ad1:xli1ei2eee12:qBt-savePath5:/datae";
+
+        let mut parser = Parser::new(&input[..]);
+
+        assert_eq!(parser.next_token(), Ok(Some(DictStart)));
+        assert_eq!(parser.next_token(), Ok(Some(Key(b"a"))));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.next_token(), Ok(Some(Key(b"qBt-savePath"))));
+        assert_eq!(parser.next_token(), Ok(Some(Bytes(b"/data"))));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.next_token(), Ok(Some(End)));
+        assert_eq!(parser.next_token(), Ok(None));
+    }
+
+    #[test]
+    fn skip_entry() {
+        let mut parser = Parser::new(b"d1:ali1ee1:bi2ee");
+
+        assert_eq!(parser.next_token(), Ok(Some(DictStart)));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.next_token(), Ok(Some(Key(b"b"))));
+    }
+
+    #[test]
+    fn same_errors_as_decoder() {
+        let inputs: [&[u8]; 14] = [
+            b"7:foo",
+            b"i-0e",
+            b"d4:infod6:lengthi001eee",
+            b"l4e",
+            b"li1e",
+            b"d3:foo",
+            b"d2:ccle2:bblee",
+            b"d3:fooe",
+            b"d3:fooi1e3:fooi2ee",
+            b"ld1:ali1ei2ei3x",
+            b"-1:a",
+            b"lllleeee",
+            b"1hello:world",
+            b"123456789012345678901:a",
+        ];
+
+        for input in inputs {
+            let settings = || [Settings::MaxDepth(3)];
+
+            let expected = settings().into_iter().fold(Decoder::new(input), Decoder::setting).decode();
+            let actual: Result<Vec<_>, _> = settings().into_iter().fold(Parser::new(input), Parser::setting).collect();
+
+            assert_eq!(actual.unwrap_err(), expected.unwrap_err(), "input = {:?}", input);
+        }
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let input = b"d1:bi1e1:ai2e1:bi3ee";
+
+        let tokens: Result<Vec<_>, _> = Parser::new(&input[..]).setting(Settings::UnsortedDictionaries).collect();
+        assert_eq!(tokens.map_err(|e| e.kind()), Err(DuplicateKey));
+
+        let tokens: Result<Vec<_>, _> = Parser::new(&input[..])
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::DuplicateKeys(DuplicateKeyPolicy::KeepLast))
+            .collect();
+        assert_eq!(tokens.map(|t| t.len()), Ok(8));
+    }
+
+    #[test]
+    fn lenient() {
+        let input = b" d 4:sizei+007e 04:name 3:foo e ";
+
+        let tokens: Result<Vec<_>, _> = Parser::new(&input[..]).setting(Settings::Lenient).collect();
+
+        assert_eq!(tokens, Ok(vec![DictStart, Key(b"size"), Int(7), Key(b"name"), Bytes(b"foo"), End]));
+
+        let mut parser = Parser::new(&input[..]).setting(Settings::Lenient);
+
+        assert_eq!(parser.next_token(), Ok(Some(DictStart)));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.skip_value(), Ok(()));
+        assert_eq!(parser.next_token(), Ok(Some(End)));
+        assert_eq!(parser.next_token(), Ok(None));
+    }
+}
@@ -0,0 +1,379 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::items::{BDictionary, BOrderedDictionary, Item};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A segment of the path to an item inside nested lists and dictionaries.
+pub enum PathSegment {
+    /// Dictionary key.
+    Key(Vec<u8>),
+    /// List index.
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Key(k) => write!(f, "{}", String::from_utf8_lossy(k)),
+            PathSegment::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(key.as_bytes().to_vec())
+    }
+}
+
+impl From<&[u8]> for PathSegment {
+    fn from(key: &[u8]) -> Self {
+        PathSegment::Key(key.to_vec())
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// Displays a path the same way it is written in a query, e.g. `info.files[3].path`.
+pub(crate) struct DisplayPath<'p>(pub(crate) &'p [PathSegment]);
+
+impl<'p> fmt::Display for DisplayPath<'p> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 && matches!(segment, PathSegment::Key(_)) {
+                write!(f, ".")?;
+            }
+
+            write!(f, "{}", segment)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A path to an item inside nested lists and dictionaries.
+///
+/// String paths consist of dot separated keys, each optionally followed by list indices,
+/// e.g. `info.files[3].path` or `[0].name`. An empty string is the empty path, which points
+/// to the item itself. Keys that contain `.` or `[` can be queried with a slice of
+/// [`PathSegment`]s instead.
+pub trait ItemPath {
+    /// Returns the segments of the path, or `None` if the path is malformed.
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>>;
+}
+
+impl ItemPath for str {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        parse_path(self).map(Cow::Owned)
+    }
+}
+
+impl ItemPath for String {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        self.as_str().segments()
+    }
+}
+
+impl ItemPath for [PathSegment] {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl<const N: usize> ItemPath for [PathSegment; N] {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl ItemPath for Vec<PathSegment> {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl ItemPath for [&str] {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        Some(Cow::Owned(self.iter().map(|&k| PathSegment::from(k)).collect()))
+    }
+}
+
+impl<const N: usize> ItemPath for [&str; N] {
+    fn segments(&self) -> Option<Cow<'_, [PathSegment]>> {
+        self[..].segments()
+    }
+}
+
+/// Parses a string path, see [`ItemPath`].
+fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
+    let mut segments = vec![];
+
+    if path.is_empty() {
+        return Some(segments);
+    }
+
+    for (i, part) in path.split('.').enumerate() {
+        let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+
+        if !key.is_empty() {
+            segments.push(PathSegment::from(key));
+        } else if i > 0 || indices.is_empty() {
+            return None;
+        }
+
+        while !indices.is_empty() {
+            let (index, rest) = indices.strip_prefix('[')?.split_once(']')?;
+
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+
+            segments.push(PathSegment::Index(index.parse().ok()?));
+            indices = rest;
+        }
+    }
+
+    Some(segments)
+}
+
+/// Returns the child of an item pointed to by a single segment.
+fn child<'i, 'a>(item: &'i Item<'a>, segment: &PathSegment) -> Option<&'i Item<'a>> {
+    match (item, segment) {
+        (Item::Dictionary(d), PathSegment::Key(k)) => d.0.get(&k[..]),
+        (Item::OrderedDictionary(d), PathSegment::Key(k)) => d.get_key(k),
+        (Item::List(l), PathSegment::Index(i)) => l.0.get(*i),
+        _ => None,
+    }
+}
+
+/// Returns the mutable child of an item pointed to by a single segment.
+fn child_mut<'i, 'a>(item: &'i mut Item<'a>, segment: &PathSegment) -> Option<&'i mut Item<'a>> {
+    match (item, segment) {
+        (Item::Dictionary(d), PathSegment::Key(k)) => d.0.get_mut(&k[..]),
+        (Item::OrderedDictionary(d), PathSegment::Key(k)) => d.get_key_mut(k),
+        (Item::List(l), PathSegment::Index(i)) => l.0.get_mut(*i),
+        _ => None,
+    }
+}
+
+/// Returns the descendant of an item pointed to by the segments.
+fn walk<'i, 'a>(mut item: &'i Item<'a>, segments: &[PathSegment]) -> Option<&'i Item<'a>> {
+    for segment in segments {
+        item = child(item, segment)?;
+    }
+
+    Some(item)
+}
+
+/// Returns the mutable descendant of an item pointed to by the segments.
+fn walk_mut<'i, 'a>(mut item: &'i mut Item<'a>, segments: &[PathSegment]) -> Option<&'i mut Item<'a>> {
+    for segment in segments {
+        item = child_mut(item, segment)?;
+    }
+
+    Some(item)
+}
+
+impl<'a> Item<'a> {
+    /// Returns a reference to the item at the path.
+    ///
+    /// Returns `None` if the path is malformed or does not exist. See [`ItemPath`] for
+    /// the path syntax.
+    ///
+    /// ```
+    /// use yabel::Decoder;
+    ///
+    /// let item = Decoder::new(b"d4:infod5:filesld4:pathl3:fooeeeee").decode_single().unwrap();
+    ///
+    /// assert_eq!(item.get_str("info.files[0].path[0]"), Some("foo"));
+    /// ```
+    pub fn get<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&Item<'a>> {
+        walk(self, &path.segments()?)
+    }
+
+    /// Returns a mutable reference to the item at the path.
+    pub fn get_mut<P: ItemPath + ?Sized>(&mut self, path: &P) -> Option<&mut Item<'a>> {
+        walk_mut(self, &path.segments()?)
+    }
+
+    /// Returns the string at the path if it is valid UTF-8.
+    pub fn get_str<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&str> {
+        self.get(path).and_then(Item::as_str)
+    }
+
+    /// Returns the bytes of the string at the path.
+    pub fn get_bytes<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&[u8]> {
+        self.get(path).and_then(Item::as_bytes)
+    }
+
+    /// Returns the integer at the path.
+    pub fn get_int<P: ItemPath + ?Sized>(&self, path: &P) -> Option<i64> {
+        self.get(path).and_then(Item::as_int)
+    }
+}
+
+impl<'a> BDictionary<'a> {
+    /// Returns a reference to the item at the path.
+    ///
+    /// The first segment of the path must be a key. See [`ItemPath`] for the path syntax.
+    pub fn get<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&Item<'a>> {
+        match path.segments()?.split_first()? {
+            (PathSegment::Key(k), rest) => walk(self.0.get(&k[..])?, rest),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the item at the path.
+    pub fn get_mut<P: ItemPath + ?Sized>(&mut self, path: &P) -> Option<&mut Item<'a>> {
+        match path.segments()?.split_first()? {
+            (PathSegment::Key(k), rest) => walk_mut(self.0.get_mut(&k[..])?, rest),
+            _ => None,
+        }
+    }
+
+    /// Returns the string at the path if it is valid UTF-8.
+    pub fn get_str<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&str> {
+        self.get(path).and_then(Item::as_str)
+    }
+
+    /// Returns the bytes of the string at the path.
+    pub fn get_bytes<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&[u8]> {
+        self.get(path).and_then(Item::as_bytes)
+    }
+
+    /// Returns the integer at the path.
+    pub fn get_int<P: ItemPath + ?Sized>(&self, path: &P) -> Option<i64> {
+        self.get(path).and_then(Item::as_int)
+    }
+}
+
+impl<'a> BOrderedDictionary<'a> {
+    /// Returns a reference to the item at the path.
+    ///
+    /// The first segment of the path must be a key. See [`ItemPath`] for the path syntax.
+    pub fn get<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&Item<'a>> {
+        match path.segments()?.split_first()? {
+            (PathSegment::Key(k), rest) => walk(self.get_key(k)?, rest),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the item at the path.
+    pub fn get_mut<P: ItemPath + ?Sized>(&mut self, path: &P) -> Option<&mut Item<'a>> {
+        match path.segments()?.split_first()? {
+            (PathSegment::Key(k), rest) => walk_mut(self.get_key_mut(k)?, rest),
+            _ => None,
+        }
+    }
+
+    /// Returns the string at the path if it is valid UTF-8.
+    pub fn get_str<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&str> {
+        self.get(path).and_then(Item::as_str)
+    }
+
+    /// Returns the bytes of the string at the path.
+    pub fn get_bytes<P: ItemPath + ?Sized>(&self, path: &P) -> Option<&[u8]> {
+        self.get(path).and_then(Item::as_bytes)
+    }
+
+    /// Returns the integer at the path.
+    pub fn get_int<P: ItemPath + ?Sized>(&self, path: &P) -> Option<i64> {
+        self.get(path).and_then(Item::as_int)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_path;
+    use crate::*;
+
+    fn torrent() -> Item<'static> {
+        Decoder::new(b"d8:announce3:url4:infod5:filesld6:lengthi1e4:pathl1:a1:beed6:lengthi2e4:pathl1:ceee4:name3:fooee")
+            .decode_single()
+            .unwrap()
+            .into_owned()
+    }
+
+    #[test]
+    fn parse() {
+        use PathSegment::*;
+
+        assert_eq!(parse_path(""), Some(vec![]));
+        assert_eq!(
+            parse_path("info.files[3].path"),
+            Some(vec![Key(b"info".to_vec()), Key(b"files".to_vec()), Index(3), Key(b"path".to_vec())])
+        );
+        assert_eq!(parse_path("[0][12]"), Some(vec![Index(0), Index(12)]));
+
+        for malformed in ["a..b", ".a", "a.", "a[", "a[]", "a[x]", "a[-1]", "a[0]b", "a.[0]"] {
+            assert_eq!(parse_path(malformed), None, "path = {:?}", malformed);
+        }
+    }
+
+    #[test]
+    fn get() {
+        let item = torrent();
+
+        assert_eq!(item.get(""), Some(&item));
+        assert_eq!(item.get_str("announce"), Some("url"));
+        assert_eq!(item.get_int("info.files[1].length"), Some(2));
+        assert_eq!(item.get_bytes("info.files[0].path[1]"), Some(&b"b"[..]));
+        assert_eq!(item.get_str(&["info", "name"]), Some("foo"));
+        assert_eq!(item.get_int(&[PathSegment::from("info"), "files".into(), 0.into(), "length".into()]), Some(1));
+
+        assert_eq!(item.get("info.files[2]"), None);
+        assert_eq!(item.get("info.name.x"), None);
+        assert_eq!(item.get_int("info.name"), None);
+        assert_eq!(item.get("info[0]"), None);
+        assert_eq!(item.get("info..name"), None);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut item = torrent();
+
+        *item.get_mut("info.files[0].length").unwrap() = 42.into();
+
+        assert_eq!(item.get_int("info.files[0].length"), Some(42));
+    }
+
+    #[test]
+    fn dictionary() {
+        let mut d = torrent().dictionary().unwrap();
+
+        assert_eq!(d.get_str("info.name"), Some("foo"));
+        assert_eq!(d.get(""), None);
+        assert_eq!(d.get("[0]"), None);
+
+        *d.get_mut("announce").unwrap() = "other".into();
+
+        assert_eq!(d.get_str(&String::from("announce")), Some("other"));
+    }
+
+    #[test]
+    fn ordered_dictionary() {
+        let item = Decoder::new(b"d1:bd1:xi1ee1:ali2eee")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        assert_eq!(item.get_int("b.x"), Some(1));
+        assert_eq!(item.get_int("a[0]"), Some(2));
+
+        let mut d = item.ordered_dictionary().unwrap();
+
+        assert_eq!(d.get_int("b.x"), Some(1));
+        assert_eq!(d.get_bytes("b"), None);
+        assert_eq!(d.get(&[PathSegment::from(&b"a"[..])]), Some(&Item::from(vec![2.into()])));
+
+        *d.get_mut("a[0]").unwrap() = "c".into();
+
+        assert_eq!(d.get_str("a[0]"), Some("c"));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::decode::{exceeds, is_number_byte, parse_integer_lenient, parse_length_lenient, Options, MAX_LENGTH_DIGITS};
+use crate::ErrorKind::*;
+use crate::{DecodeError, DeviationKind, ErrorKind, Item};
+
+/// Reads strings and integers from a byte buffer, the part shared by the
+/// [`Decoder`](crate::Decoder) and the [`Parser`](crate::Parser).
+///
+/// Errors carry no path, it is added by the caller.
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) cursor: usize,
+    pub(crate) options: Options,
+    items: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8], options: Options) -> Self {
+        Self {
+            bytes,
+            cursor: 0,
+            options,
+            items: 0,
+        }
+    }
+
+    /// Constructs an error at `offset`, with the offending bytes.
+    fn error(&self, kind: ErrorKind, offset: usize, bytes: &[u8]) -> DecodeError {
+        DecodeError::new(kind, offset).with_bytes(bytes)
+    }
+
+    /// Counts an item starting at the cursor position against the item limit.
+    pub(crate) fn count_item(&mut self) -> Result<(), DecodeError> {
+        self.items += 1;
+
+        if exceeds(self.items, self.options.limits.items) {
+            return Err(self.error(ItemLimitExceeded, self.cursor, &self.bytes[self.cursor..=self.cursor]));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the bytes of a number until `stop_byte` is reached and returns the read bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidData`] at the first byte that can not be a part of a number, or at the
+    /// first byte past `max_length`, and [`UnexpectedEndOfBuffer`] if `stop_byte` was not
+    /// reached.
+    fn read_number(&mut self, start: usize, stop_byte: u8, max_length: Option<usize>) -> Result<&'a [u8], DecodeError> {
+        let bytes = self.bytes;
+        let rest = &bytes[self.cursor..];
+
+        for (i, b) in rest.iter().enumerate() {
+            if *b == stop_byte {
+                self.cursor += i + 1;
+
+                return Ok(&rest[..i]);
+            }
+
+            if !is_number_byte(*b) || exceeds(i + 1, max_length) {
+                return Err(self.error(InvalidData, start, &bytes[start..=self.cursor + i]));
+            }
+        }
+
+        Err(self.error(UnexpectedEndOfBuffer, self.cursor, rest))
+    }
+
+    /// Reads a string starting at the cursor position and returns its bytes.
+    ///
+    /// Every tolerated deviation is passed to `deviation`.
+    pub(crate) fn read_string(&mut self, deviation: impl FnMut(DeviationKind)) -> Result<&'a [u8], DecodeError> {
+        let start = self.cursor;
+
+        let prefix = self.read_number(start, b':', Some(MAX_LENGTH_DIGITS))?;
+
+        let length = parse_length_lenient(prefix, self.options.leniency, deviation)
+            .map_err(|kind| self.error(kind, start, prefix))?;
+
+        if exceeds(length, self.options.limits.string_length) {
+            return Err(self.error(StringLengthLimitExceeded, start, prefix));
+        }
+
+        let bytes = self.bytes;
+
+        let s = self
+            .cursor
+            .checked_add(length)
+            .and_then(|end| bytes.get(self.cursor..end))
+            .ok_or_else(|| self.error(UnexpectedEndOfBuffer, start, &bytes[start..self.cursor]))?;
+
+        self.cursor += length;
+
+        Ok(s)
+    }
+
+    /// Reads an integer starting at the cursor position and returns it together with its
+    /// digits, as written in the input.
+    ///
+    /// Every tolerated deviation is passed to `deviation`.
+    pub(crate) fn read_integer(
+        &mut self,
+        deviation: impl FnMut(DeviationKind),
+    ) -> Result<(Item<'static>, &'a [u8]), DecodeError> {
+        let start = self.cursor;
+
+        self.cursor += 1;
+
+        let digits = self.read_number(start, b'e', None)?;
+
+        let i = parse_integer_lenient(digits, self.options.leniency, deviation)
+            .map_err(|kind| self.error(kind, start, &self.bytes[start..self.cursor]))?;
+
+        Ok((i, digits))
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::ser::{self, Serialize};
+
+use crate::items::{BDictionary, BList, BString, Item};
+use crate::EncodeError;
+
+/// Serializes a value into bencode.
+///
+/// Structs and maps become dictionaries with sorted keys, sequences and tuples become
+/// lists, byte slices (see [`serde_bytes`](https://docs.rs/serde_bytes)) and strings become
+/// byte strings, booleans become `0` or `1`. Fields that are `None` or `()` are omitted.
+/// Enum variants are encoded as a string for unit variants, or as a dictionary with a single
+/// key otherwise.
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Ping<'a> {
+///     t: &'a str,
+///     y: &'a str,
+///     q: &'a str,
+/// }
+///
+/// let bytes = yabel::to_bytes(&Ping { t: "aa", y: "q", q: "ping" }).unwrap();
+///
+/// assert_eq!(bytes, b"d1:q4:ping1:t2:aa1:y1:qe");
+/// ```
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let item = to_item(value)?;
+    let mut bytes = Vec::with_capacity(item.encoded_len());
+
+    item.encode_to(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Serializes a value into bencode and writes it into the writer.
+///
+/// The value is serialized into an [`Item`] first, so that dictionary keys can be sorted,
+/// and nothing is written until it is complete. This is not a streaming serializer, the
+/// memory used grows with the size of the value. See [`to_bytes`] for details.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(w: &mut W, value: &T) -> Result<(), EncodeError> {
+    Ok(to_item(value)?.encode_to(w)?)
+}
+
+/// Serializes a value into an item.
+fn to_item<T: Serialize + ?Sized>(value: &T) -> Result<Item<'static>, EncodeError> {
+    value.serialize(Serializer)?.ok_or(EncodeError::UnsupportedValue("none"))
+}
+
+impl ser::Error for EncodeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        EncodeError::Custom(msg.to_string())
+    }
+}
+
+/// Integers out of the `i64` range become [`BBigInteger`](crate::BBigInteger)s.
+fn integer<T: Into<Item<'static>>>(i: T) -> Result<Option<Item<'static>>, EncodeError> {
+    Ok(Some(i.into()))
+}
+
+fn string(bytes: Vec<u8>) -> Result<Option<Item<'static>>, EncodeError> {
+    Ok(Some(Item::String(BString::from(bytes))))
+}
+
+/// Wraps a value into a single entry dictionary keyed by the variant name.
+fn variant(name: &'static str, value: Item<'static>) -> Option<Item<'static>> {
+    let mut map = BTreeMap::new();
+    map.insert(BString::from(name), value);
+
+    Some(Item::Dictionary(BDictionary(map)))
+}
+
+/// Serializes values into items.
+///
+/// `None` stands for a value that has no representation on its own, such as `None` or `()`,
+/// and is omitted from dictionaries.
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        integer(v as i64)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Err(EncodeError::UnsupportedValue("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Err(EncodeError::UnsupportedValue("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        string(v.to_string().into_bytes())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        string(v.as_bytes().to_vec())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        string(v.to_vec())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant(variant_name, to_item(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(VariantSerializer(variant, self.serialize_seq(Some(len))?))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(MapSerializer::default())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(MapSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(VariantSerializer(variant, MapSerializer::default()))
+    }
+}
+
+struct SeqSerializer(Vec<Item<'static>>);
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.push(to_item(value)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(Item::List(BList(self.0))))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+#[derive(Default)]
+struct MapSerializer {
+    map: BTreeMap<BString<'static>, Item<'static>>,
+    key: Option<BString<'static>>,
+}
+
+impl MapSerializer {
+    fn insert(&mut self, key: BString<'static>, value: Option<Item<'static>>) -> Result<(), EncodeError> {
+        if let Some(value) = value {
+            match self.map.entry(key) {
+                Entry::Vacant(e) => {
+                    e.insert(value);
+                },
+                Entry::Occupied(_) => return Err(EncodeError::DuplicateKey),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let key = match key.serialize(Serializer)? {
+            Some(Item::String(s)) => s,
+            Some(Item::Integer(i)) => BString::from(i.0.to_string()),
+            Some(Item::BigInteger(i)) => BString::from(i.to_string()),
+            _ => return Err(EncodeError::InvalidDictionaryKey),
+        };
+
+        self.key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().expect("serialize_value is called after serialize_key");
+
+        self.insert(key, value.serialize(Serializer)?)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(Item::Dictionary(BDictionary(self.map))))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        self.insert(BString::from(key), value.serialize(Serializer)?)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Serializes the contents of an enum variant, wrapping them into a single entry dictionary.
+struct VariantSerializer<S>(&'static str, S);
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(&mut self.1, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(variant(self.0, Item::List(BList(self.1 .0))))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = Option<Item<'static>>;
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(variant(self.0, Item::Dictionary(BDictionary(self.1.map))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Serialize;
+
+    use crate::{to_bytes, to_writer, EncodeError};
+
+    #[derive(Serialize)]
+    struct File<'a> {
+        length: u64,
+        #[serde(with = "serde_bytes")]
+        md5sum: &'a [u8],
+        path: Vec<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        attr: Option<&'a str>,
+        private: Option<bool>,
+    }
+
+    #[derive(Serialize)]
+    enum Message {
+        Ping,
+        Get(u32),
+        Pair(i8, i8),
+        Piece { index: u32 },
+    }
+
+    #[test]
+    fn structs() {
+        let file = File {
+            length: 42,
+            md5sum: b"\x00\xff",
+            path: vec!["a", "b"],
+            attr: None,
+            private: Some(true),
+        };
+
+        assert_eq!(to_bytes(&file).unwrap(), b"d6:lengthi42e6:md5sum2:\x00\xff4:pathl1:a1:be7:privatei1ee");
+    }
+
+    #[test]
+    fn enums() {
+        let messages = [Message::Ping, Message::Get(1), Message::Pair(-1, 2), Message::Piece { index: 3 }];
+
+        assert_eq!(to_bytes(&messages).unwrap(), b"l4:Pingd3:Geti1eed4:Pairli-1ei2eeed5:Pieced5:indexi3eeee");
+    }
+
+    #[test]
+    fn maps_are_sorted() {
+        let map: HashMap<_, _> = [("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+
+        assert_eq!(to_bytes(&map).unwrap(), b"d1:ai1e1:bi2e1:ci3ee");
+
+        let map: BTreeMap<_, _> = [(10, "x"), (9, "y")].into_iter().collect();
+
+        assert_eq!(to_bytes(&map).unwrap(), b"d2:101:x1:91:ye");
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(to_bytes(&u64::MAX).unwrap(), b"i18446744073709551615e");
+        assert_eq!(to_bytes(&i128::MIN).unwrap(), b"i-170141183460469231731687303715884105728e");
+        assert_eq!(to_bytes(&1_u128).unwrap(), b"i1e");
+
+        let map: BTreeMap<_, _> = [(u64::MAX, 1)].into_iter().collect();
+
+        assert_eq!(to_bytes(&map).unwrap(), b"d20:18446744073709551615i1ee");
+    }
+
+    #[test]
+    fn writer() {
+        let mut w = vec![];
+
+        to_writer(&mut w, &(1, "two", [3u8])).unwrap();
+
+        assert_eq!(w, b"li1e3:twoli3eee");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(to_bytes(&1.5), Err(EncodeError::UnsupportedValue("f64"))));
+        assert!(matches!(to_bytes(&None::<u8>), Err(EncodeError::UnsupportedValue("none"))));
+        assert!(matches!(to_bytes(&[None::<u8>]), Err(EncodeError::UnsupportedValue("none"))));
+
+        let map: BTreeMap<_, _> = [((1, 2), 3)].into_iter().collect();
+
+        assert!(matches!(to_bytes(&map), Err(EncodeError::InvalidDictionaryKey)));
+
+        assert!(matches!(to_bytes(&DuplicateKeys(&[("1", 1), ("1", 2)])), Err(EncodeError::DuplicateKey)));
+    }
+
+    /// Serializes pairs as a map without checking for duplicates.
+    struct DuplicateKeys<'a>(&'a [(&'a str, i32)]);
+
+    impl Serialize for DuplicateKeys<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::decode::Options;
+use crate::frame::{Framer, Status};
+use crate::items::Item;
+use crate::{DecodeError, Decoder, Settings};
+
+/// Bencode decoder that reads its input from a [`BufRead`].
+///
+/// Top level items are decoded one at a time: only the bytes of the current item are kept
+/// in memory, and the decoded items own their data. Decoding follows exactly the same rules
+/// as [`Decoder`] does, and reports the same errors.
+///
+/// I/O errors are reported as [`ErrorKind::Io`](crate::ErrorKind::Io), except for
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof), which is reported as
+/// [`ErrorKind::UnexpectedEndOfBuffer`](crate::ErrorKind::UnexpectedEndOfBuffer).
+pub struct StreamDecoder<R> {
+    reader: R,
+    options: Options,
+    buffer: Vec<u8>,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: Read> StreamDecoder<BufReader<R>> {
+    /// Constructs a new `StreamDecoder` that reads from an unbuffered reader.
+    pub fn from_reader(reader: R) -> Self {
+        Self::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> StreamDecoder<R> {
+    /// Constructs a new `StreamDecoder` with specified reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            options: Options::default(),
+            buffer: vec![],
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Applies a setting for the current decoder.
+    ///
+    /// See [`Settings`] enum for a full list.
+    pub fn setting(self, setting: Settings) -> Self {
+        let mut s = self;
+
+        s.options.apply(setting);
+
+        s
+    }
+
+    /// Decodes the next top level item.
+    ///
+    /// Returns `Ok(None)` if the reader is exhausted. Once an error is returned, all subsequent
+    /// calls return `Ok(None)`. Error offsets are counted from the beginning of the stream.
+    pub fn decode_next(&mut self) -> Result<Option<Item<'static>>, DecodeError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let result = self.read_item().and_then(|_| {
+            Decoder::with_options(&self.buffer, self.options.clone())
+                .decode_next()
+                .map(|item| item.map(Item::into_owned))
+                .map_err(|e| e.shift(self.offset))
+        });
+
+        self.offset += self.buffer.len();
+        self.buffer.clear();
+
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+
+        result
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the bytes of the next top level item into the buffer.
+    ///
+    /// Reading stops early at invalid input, leaving the error reporting to the [`Decoder`].
+    fn read_item(&mut self) -> Result<(), DecodeError> {
+        let mut framer = Framer::new(self.options.limits, self.options.leniency);
+
+        loop {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DecodeError::from(e).shift(self.offset + self.buffer.len())),
+            };
+
+            if chunk.is_empty() {
+                return Ok(());
+            }
+
+            let (consumed, status) = framer.feed(chunk);
+
+            self.buffer.extend_from_slice(&chunk[..consumed]);
+            self.reader.consume(consumed);
+
+            if status != Status::Incomplete {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StreamDecoder<R> {
+    type Item = Result<Item<'static>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decode_next().transpose()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+/// The result of feeding bytes to a [`PushDecoder`].
+pub enum Progress {
+    /// The item is incomplete, all fed bytes were consumed.
+    NeedMoreData,
+    /// The item is complete.
+    Done {
+        /// The decoded item.
+        item: Item<'static>,
+        /// The number of bytes consumed from the last fed chunk. Bytes past this point
+        /// do not belong to the item.
+        consumed: usize,
+    },
+}
+
+/// Resumable bencode decoder for input that arrives in chunks.
+///
+/// Bytes are fed as they arrive, the decoder keeps the bytes of an incomplete item and
+/// continues where it left off when more data is available. Decoding follows exactly the same
+/// rules as [`Decoder`] does, and reports the same errors.
+///
+/// After an item is completed or an error is returned, the decoder is ready for the next item.
+#[derive(Default)]
+pub struct PushDecoder {
+    options: Options,
+    framer: Option<Framer>,
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+impl PushDecoder {
+    /// Constructs a new `PushDecoder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a setting for the current decoder.
+    ///
+    /// See [`Settings`] enum for a full list.
+    pub fn setting(self, setting: Settings) -> Self {
+        let mut s = self;
+
+        s.options.apply(setting);
+
+        s
+    }
+
+    /// Returns the number of buffered bytes of the incomplete item.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Feeds a chunk of bytes.
+    ///
+    /// Error offsets are counted from the first byte ever fed. Errors also report the number
+    /// of bytes consumed from `chunk`, see [`DecodeError::consumed`].
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Progress, DecodeError> {
+        let (limits, leniency) = (self.options.limits, self.options.leniency);
+
+        let (consumed, status) = self
+            .framer
+            .get_or_insert_with(|| Framer::new(limits, leniency))
+            .feed(chunk);
+
+        self.buffer.extend_from_slice(&chunk[..consumed]);
+
+        if status == Status::Incomplete {
+            return Ok(Progress::NeedMoreData);
+        }
+
+        let result = Decoder::with_options(&self.buffer, self.options.clone())
+            .decode_next()
+            .map(|item| Progress::Done {
+                item: item.expect("framed item is not empty").into_owned(),
+                consumed,
+            })
+            .map_err(|e| e.shift(self.offset).with_consumed(consumed));
+
+        self.offset += self.buffer.len();
+        self.framer = None;
+        self.buffer.clear();
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use crate::items::*;
+    use crate::ErrorKind::*;
+    use crate::{Decoder, Progress, PushDecoder, Settings, StreamDecoder};
+
+    /// A reader that returns at most one byte per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(b)) => {
+                    *b = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                },
+                _ => Ok(0),
+            }
+        }
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+        }
+    }
+
+    fn decode_all(input: &[u8]) -> Vec<Result<Item<'static>, crate::ErrorKind>> {
+        StreamDecoder::new(BufReader::with_capacity(1, Trickle(input)))
+            .map(|r| r.map_err(|e| e.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn same_items_as_slice_decoder() {
+        let input = b"d3:bar4:spam3:fooli42ei-7eee4:spami0ele";
+
+        let expected = Decoder::new(input).decode().unwrap();
+        let actual: Vec<_> = decode_all(input).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(decode_all(b"").is_empty());
+    }
+
+    #[test]
+    fn same_errors_as_slice_decoder() {
+        let inputs: [&[u8]; 11] = [
+            b"7:foo",
+            b"i-0e",
+            b"i001e",
+            b"l4e",
+            b"li00002ee",
+            b"d2:ccle2:bblee",
+            b"d3:fooe",
+            b"-1:a",
+            b"1hello:world",
+            b"li1xe",
+            b"123456789012345678901:a",
+        ];
+
+        for input in inputs {
+            let expected = Decoder::new(input).decode().unwrap_err().kind();
+
+            assert_eq!(decode_all(input).pop(), Some(Err(expected)), "input = {:?}", input);
+        }
+    }
+
+    #[test]
+    fn items_before_an_error() {
+        let actual = decode_all(b"i1e3:fooxi2e");
+
+        assert_eq!(actual, vec![Ok(1.into()), Ok("foo".into()), Err(UnexpectedByte(b'x'))]);
+    }
+
+    #[test]
+    fn offsets_are_counted_from_the_beginning() {
+        let input = b"i1e3:food3:bar3:baz3:fooi001ee";
+
+        let mut decoder = StreamDecoder::from_reader(&input[..]);
+
+        decoder.decode_next().unwrap();
+        decoder.decode_next().unwrap();
+
+        let e = decoder.decode_next().unwrap_err();
+
+        assert_eq!(e, Decoder::new(input).decode().unwrap_err());
+        assert_eq!(e.offset(), 24);
+    }
+
+    #[test]
+    fn settings() {
+        let mut decoder =
+            StreamDecoder::from_reader(&b"d2:ccle2:bblee"[..]).setting(Settings::UnsortedDictionaries);
+
+        assert!(decoder.decode_next().unwrap().is_some());
+        assert!(decoder.decode_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn limits() {
+        let settings = || {
+            [
+                Settings::MaxDepth(2),
+                Settings::MaxStringLength(4),
+                Settings::MaxItems(3),
+                Settings::MaxBytes(8),
+            ]
+        };
+        let inputs: [&[u8]; 4] = [b"llleee", b"5:abcde", b"l0:0:0:e", b"l2:ab2:abe"];
+
+        for input in inputs {
+            let expected = settings().into_iter().fold(Decoder::new(input), Decoder::setting).decode();
+
+            let actual = settings()
+                .into_iter()
+                .fold(StreamDecoder::new(BufReader::with_capacity(1, Trickle(input))), StreamDecoder::setting)
+                .decode_next();
+
+            assert_eq!(actual.unwrap_err(), expected.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn lenient() {
+        let input = b" d 4:sizei+007e 04:name 3:foo e i-0e\n";
+
+        let expected = Decoder::new(input).setting(Settings::Lenient).decode().unwrap();
+
+        let actual: Result<Vec<_>, _> =
+            StreamDecoder::new(BufReader::with_capacity(1, Trickle(input))).setting(Settings::Lenient).collect();
+
+        assert_eq!(actual.unwrap(), expected);
+
+        let mut decoder = PushDecoder::new().setting(Settings::Lenient);
+
+        assert_eq!(decoder.feed(b" l 01:a "), Ok(Progress::NeedMoreData));
+        assert_eq!(decoder.feed(b"e"), Ok(Progress::Done { item: vec!["a".into()].into(), consumed: 1 }));
+    }
+
+    #[test]
+    fn unbounded_input_is_not_buffered() {
+        let mut decoder = StreamDecoder::from_reader(io::repeat(b'l')).setting(Settings::MaxDepth(512));
+
+        assert_eq!(decoder.decode_next().map_err(|e| e.kind()), Err(DepthLimitExceeded));
+    }
+
+    #[test]
+    fn push_decoder_chunks() {
+        let input = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe";
+
+        for size in 1..input.len() {
+            let mut decoder = PushDecoder::new();
+            let mut chunks = input.chunks(size).peekable();
+
+            while let Some(chunk) = chunks.next() {
+                let progress = decoder.feed(chunk).unwrap();
+
+                if chunks.peek().is_some() {
+                    assert_eq!(progress, Progress::NeedMoreData);
+                } else {
+                    let expected = Decoder::new(input).decode().unwrap().pop().unwrap();
+
+                    assert_eq!(progress, Progress::Done { item: expected, consumed: chunk.len() });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn push_decoder_trailing_payload() {
+        let mut decoder = PushDecoder::new();
+
+        assert_eq!(decoder.feed(b"d8:msg_typei1e5:piecei0").unwrap(), Progress::NeedMoreData);
+        assert_eq!(decoder.buffered(), 23);
+
+        let chunk = b"eepayload";
+
+        match decoder.feed(chunk).unwrap() {
+            Progress::Done { consumed, .. } => assert_eq!(&chunk[consumed..], b"payload"),
+            Progress::NeedMoreData => panic!("the item is complete"),
+        }
+
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn push_decoder_errors() {
+        let mut decoder = PushDecoder::new().setting(Settings::MaxStringLength(16));
+
+        assert_eq!(decoder.feed(b"i1e").map(|_| ()), Ok(()));
+
+        let e = decoder.feed(b"l999:").unwrap_err();
+
+        assert_eq!(e.kind(), StringLengthLimitExceeded);
+        assert_eq!(e.offset(), 4);
+        assert_eq!(e.consumed(), Some(5));
+
+        assert_eq!(decoder.feed(b"i2e"), Ok(Progress::Done { item: 2.into(), consumed: 3 }));
+
+        // the rest of the chunk is left for the next item
+        let chunk = b"i1xi3e";
+        let e = decoder.feed(chunk).unwrap_err();
+
+        assert_eq!((e.kind(), e.consumed()), (InvalidData, Some(3)));
+        assert_eq!(decoder.feed(&chunk[3..]), Ok(Progress::Done { item: 3.into(), consumed: 3 }));
+    }
+
+    #[test]
+    fn io_error() {
+        let mut decoder = StreamDecoder::from_reader(Broken);
+
+        let e = decoder.decode_next().unwrap_err();
+
+        assert_eq!(e.kind(), Io(io::ErrorKind::ConnectionReset));
+        assert_eq!(e.to_string(), "i/o error: connection reset");
+        assert_eq!(decoder.decode_next(), Ok(None));
+    }
+}
@@ -0,0 +1,716 @@
+use std::fmt::{self, Write};
+
+use crate::decode::{exceeds, parse_integer_lenient, Leniency};
+use crate::items::*;
+use crate::util::{parse_hex, write_hex, write_quoted, Escape, MAX_DEPTH};
+use crate::ErrorKind::*;
+use crate::{ErrorKind, TextError};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// How [`Printer`] prints byte strings that are not valid UTF-8.
+pub enum BinaryStyle {
+    /// A hex literal, e.g. `x"00ff"`.
+    #[default]
+    Hex,
+    /// A string literal with non-ASCII and control bytes escaped, e.g. `"\x00\xff"`.
+    Escaped,
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+/// Printer settings.
+pub enum PrintSettings {
+    /// Indents nested items by this many spaces, 2 by default.
+    Indent(usize),
+    /// Sets how byte strings that are not valid UTF-8 are printed, [`BinaryStyle::Hex`] by
+    /// default.
+    Binary(BinaryStyle),
+    /// Prints strings longer than this many bytes as just their length, e.g. `<400000 bytes>`,
+    /// whether they are valid UTF-8 or not.
+    ///
+    /// The `pieces` string of a torrent is printed as the number of its 20 byte hashes, e.g.
+    /// `<20000 × 20 bytes>`. Dictionary keys are never truncated.
+    MaxStringLength(usize),
+    /// Prints at most this many items of a list, followed by the number of elided items,
+    /// e.g. `<998 more items>`.
+    MaxListItems(usize),
+    /// Prints the keys of [`BOrderedDictionary`] in their original order rather than sorted.
+    KeepKeyOrder,
+}
+
+#[derive(Clone, Debug)]
+/// Pretty printer for items.
+///
+/// Items are printed in a JSON-like notation, one list item or dictionary entry per line,
+/// with dictionary keys sorted. Strings that are valid UTF-8 are printed as string literals,
+/// other byte strings as configured with [`PrintSettings::Binary`]. The [`Display`](fmt::Display)
+/// implementation of [`Item`] uses the default settings.
+///
+/// Unless strings are truncated or lists are elided, the output can be parsed back with
+/// [`from_text`].
+///
+/// ```
+/// use yabel::{bencode, PrintSettings, Printer};
+///
+/// let torrent = bencode!({
+///     "announce": "http://tracker/announce",
+///     "info": { "name": "foo", "length": 7, "pieces": &[0xffu8; 20 * 20000] },
+/// });
+///
+/// let dump = Printer::new()
+///     .setting(PrintSettings::MaxStringLength(32))
+///     .print(&torrent);
+///
+/// assert_eq!(
+///     dump,
+///     r#"{
+///   "announce": "http://tracker/announce",
+///   "info": {
+///     "length": 7,
+///     "name": "foo",
+///     "pieces": <20000 × 20 bytes>
+///   }
+/// }"#
+/// );
+/// ```
+pub struct Printer {
+    indent: usize,
+    binary: BinaryStyle,
+    max_string_length: Option<usize>,
+    max_list_items: Option<usize>,
+    keep_key_order: bool,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            binary: BinaryStyle::default(),
+            max_string_length: None,
+            max_list_items: None,
+            keep_key_order: false,
+        }
+    }
+}
+
+impl Printer {
+    /// Constructs a new `Printer` with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a setting for the current printer.
+    ///
+    /// See [`PrintSettings`] enum for a full list.
+    pub fn setting(self, setting: PrintSettings) -> Self {
+        let mut s = self;
+
+        match setting {
+            PrintSettings::Indent(n) => s.indent = n,
+            PrintSettings::Binary(style) => s.binary = style,
+            PrintSettings::MaxStringLength(n) => s.max_string_length = Some(n),
+            PrintSettings::MaxListItems(n) => s.max_list_items = Some(n),
+            PrintSettings::KeepKeyOrder => s.keep_key_order = true,
+        }
+
+        s
+    }
+
+    /// Prints the item into a string.
+    pub fn print(&self, item: &Item) -> String {
+        let mut out = String::new();
+
+        self.write_to(&mut out, item).expect("writing into a string does not fail");
+
+        out
+    }
+
+    /// Prints the item into `w`.
+    pub fn write_to<W: Write>(&self, w: &mut W, item: &Item) -> fmt::Result {
+        self.write_item(w, item, 0)
+    }
+
+    fn write_item<W: Write>(&self, w: &mut W, item: &Item, depth: usize) -> fmt::Result {
+        match item {
+            Item::String(s) => self.write_string(w, s, false),
+            Item::Integer(i) => write!(w, "{}", i.0),
+            Item::BigInteger(i) => write!(w, "{}", i),
+            Item::List(l) => self.write_list(w, &l.0, depth),
+            Item::Dictionary(d) => self.write_dict(w, d.0.iter().collect(), depth),
+            Item::OrderedDictionary(d) => {
+                let mut pairs: Vec<_> = d.0.iter().map(|(k, v)| (k, v)).collect();
+
+                if !self.keep_key_order {
+                    pairs.sort_by_key(|(k, _)| *k);
+                }
+
+                self.write_dict(w, pairs, depth)
+            },
+        }
+    }
+
+    fn write_list<W: Write>(&self, w: &mut W, items: &[Item], depth: usize) -> fmt::Result {
+        if items.is_empty() {
+            return w.write_str("[]");
+        }
+
+        let shown = self.max_list_items.map_or(items.len(), |n| n.min(items.len()));
+
+        w.write_char('[')?;
+
+        for (i, item) in items[..shown].iter().enumerate() {
+            if i > 0 {
+                w.write_char(',')?;
+            }
+
+            self.newline(w, depth + 1)?;
+            self.write_item(w, item, depth + 1)?;
+        }
+
+        if shown < items.len() {
+            if shown > 0 {
+                w.write_char(',')?;
+            }
+
+            self.newline(w, depth + 1)?;
+
+            let elided = items.len() - shown;
+            let plural = if elided == 1 { "" } else { "s" };
+
+            match shown {
+                0 => write!(w, "<{} item{}>", elided, plural)?,
+                _ => write!(w, "<{} more item{}>", elided, plural)?,
+            }
+        }
+
+        self.newline(w, depth)?;
+        w.write_char(']')
+    }
+
+    fn write_dict<W: Write>(&self, w: &mut W, pairs: Vec<(&BString, &Item)>, depth: usize) -> fmt::Result {
+        if pairs.is_empty() {
+            return w.write_str("{}");
+        }
+
+        w.write_char('{')?;
+
+        for (i, (k, v)) in pairs.into_iter().enumerate() {
+            if i > 0 {
+                w.write_char(',')?;
+            }
+
+            self.newline(w, depth + 1)?;
+            self.write_bytes(w, k)?;
+            w.write_str(": ")?;
+
+            match v {
+                Item::String(s) => self.write_string(w, s, k.0[..] == *b"pieces")?,
+                v => self.write_item(w, v, depth + 1)?,
+            }
+        }
+
+        self.newline(w, depth)?;
+        w.write_char('}')
+    }
+
+    /// Writes a string value, truncated if it is too long. `hashes` is `true` for `pieces`.
+    fn write_string<W: Write>(&self, w: &mut W, s: &BString, hashes: bool) -> fmt::Result {
+        match s.0.len() {
+            len if !exceeds(len, self.max_string_length) => self.write_bytes(w, s),
+            len if hashes && len % 20 == 0 => write!(w, "<{} × 20 bytes>", len / 20),
+            len => write!(w, "<{} bytes>", len),
+        }
+    }
+
+    /// Writes a byte string in full.
+    fn write_bytes<W: Write>(&self, w: &mut W, s: &BString) -> fmt::Result {
+        if let Some(s) = s.as_str() {
+            return write_quoted(w, s, Escape::Text);
+        }
+
+        match self.binary {
+            BinaryStyle::Escaped => write!(w, "\"{}\"", s.0.escape_ascii()),
+            BinaryStyle::Hex => {
+                w.write_str("x\"")?;
+                write_hex(w, &s.0)?;
+                w.write_char('"')
+            },
+        }
+    }
+
+    fn newline<W: Write>(&self, w: &mut W, depth: usize) -> fmt::Result {
+        write!(w, "\n{:1$}", "", depth * self.indent)
+    }
+}
+
+impl fmt::Display for Item<'_> {
+    /// Pretty prints the item with the default [`Printer`] settings.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Printer::new().write_to(f, self)
+    }
+}
+
+/// Parses the text notation into an item.
+///
+/// The notation is the one written by [`Printer`]:
+///
+/// * integers of any size, e.g. `-42`;
+/// * string literals, e.g. `"name"`, with `\"`, `\\`, `\'`, `\n`, `\r`, `\t`, `\0` and `\xff`
+///   escapes, the latter standing for arbitrary bytes;
+/// * hex literals, e.g. `x"00ff"`, which may contain whitespace;
+/// * lists, e.g. `[1, "a"]`;
+/// * dictionaries with string or hex literals as keys, e.g. `{ "a": 1, x"ff": [] }`.
+///
+/// Commas may trail the last item of a list or a dictionary, and `#` starts a comment that
+/// runs to the end of the line. Dictionaries are decoded into [`BDictionary`], just as
+/// [`Decoder`](crate::Decoder) does.
+///
+/// ```
+/// use yabel::{from_text, Bencode};
+///
+/// let item = from_text(r#"
+///     ## a fixture
+///     {
+///         "announce": "http://tracker/announce",
+///         "info": { "length": 123, "pieces": x"ab12 cd34" },
+///     }
+/// "#)
+/// .unwrap();
+///
+/// assert_eq!(item.encode(), b"d8:announce23:http://tracker/announce4:infod6:lengthi123e6:pieces4:\xab\x12\xcd\x34ee");
+/// ```
+///
+/// # Errors
+///
+/// Returns an error with the line and the column of the offending character if the input
+/// is malformed, has duplicate keys, or integers with leading zeros. Lists and dictionaries
+/// nested deeper than 256 levels are rejected with [`ErrorKind::DepthLimitExceeded`].
+pub fn from_text(text: &str) -> Result<Item<'static>, TextError> {
+    let mut parser = TextParser {
+        text,
+        bytes: text.as_bytes(),
+        cursor: 0,
+        depth: 0,
+    };
+
+    let item = parser.parse_value()?;
+
+    parser.skip_whitespace();
+
+    match parser.bytes.get(parser.cursor) {
+        None => Ok(item),
+        Some(_) => Err(parser.error(TrailingData, parser.cursor)),
+    }
+}
+
+/// Parses the text notation into items.
+struct TextParser<'t> {
+    text: &'t str,
+    bytes: &'t [u8],
+    cursor: usize,
+    depth: usize,
+}
+
+impl<'t> TextParser<'t> {
+    fn error(&self, kind: ErrorKind, offset: usize) -> TextError {
+        TextError::new(kind, self.text, offset)
+    }
+
+    /// Returns an error for the next byte, or for the end of the input.
+    fn unexpected(&self) -> TextError {
+        match self.bytes.get(self.cursor) {
+            Some(b) => self.error(UnexpectedByte(*b), self.cursor),
+            None => self.error(UnexpectedEndOfBuffer, self.cursor),
+        }
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.bytes.get(self.cursor) {
+            match b {
+                b' ' | b'\t' | b'\n' | b'\r' => self.cursor += 1,
+                b'#' => {
+                    while self.bytes.get(self.cursor).is_some_and(|b| *b != b'\n') {
+                        self.cursor += 1;
+                    }
+                },
+                _ => break,
+            }
+        }
+    }
+
+    /// Consumes `byte` after optional whitespace, if it is the next one.
+    fn consume(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+
+        let found = self.bytes.get(self.cursor) == Some(&byte);
+
+        if found {
+            self.cursor += 1;
+        }
+
+        found
+    }
+
+    fn parse_value(&mut self) -> Result<Item<'static>, TextError> {
+        self.skip_whitespace();
+
+        match self.bytes.get(self.cursor) {
+            Some(b'"' | b'x') => self.parse_bytes().map(Item::String),
+            Some(b'-' | b'0'..=b'9') => self.parse_integer(),
+            Some(b'[') => self.nested(Self::parse_list),
+            Some(b'{') => self.nested(Self::parse_dict),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses a list or a dictionary one level deeper.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Item<'static>, TextError>) -> Result<Item<'static>, TextError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(DepthLimitExceeded, self.cursor));
+        }
+
+        self.depth += 1;
+
+        let item = parse(self);
+
+        self.depth -= 1;
+
+        item
+    }
+
+    fn parse_integer(&mut self) -> Result<Item<'static>, TextError> {
+        let start = self.cursor;
+
+        self.cursor += 1;
+
+        while let Some(b'0'..=b'9') = self.bytes.get(self.cursor) {
+            self.cursor += 1;
+        }
+
+        parse_integer_lenient(&self.bytes[start..self.cursor], Leniency::default(), |_| {})
+            .map_err(|kind| self.error(kind, start))
+    }
+
+    fn parse_list(&mut self) -> Result<Item<'static>, TextError> {
+        self.cursor += 1;
+
+        let mut items = vec![];
+
+        while !self.consume(b']') {
+            items.push(self.parse_value()?);
+
+            if self.consume(b']') {
+                break;
+            } else if !self.consume(b',') {
+                return Err(self.unexpected());
+            }
+        }
+
+        Ok(Item::List(BList(items)))
+    }
+
+    fn parse_dict(&mut self) -> Result<Item<'static>, TextError> {
+        self.cursor += 1;
+
+        let mut dict = BDictionary::default();
+
+        while !self.consume(b'}') {
+            let start = self.cursor;
+
+            let key = match self.bytes.get(self.cursor) {
+                Some(b'"' | b'x') => self.parse_bytes()?,
+                _ => return Err(self.unexpected()),
+            };
+
+            if !self.consume(b':') {
+                return Err(self.unexpected());
+            }
+
+            let value = self.parse_value()?;
+
+            if dict.0.insert(key, value).is_some() {
+                return Err(self.error(DuplicateKey, start));
+            }
+
+            if self.consume(b'}') {
+                break;
+            } else if !self.consume(b',') {
+                return Err(self.unexpected());
+            }
+        }
+
+        Ok(Item::Dictionary(dict))
+    }
+
+    /// Parses a string or a hex literal, the cursor is at its first byte.
+    fn parse_bytes(&mut self) -> Result<BString<'static>, TextError> {
+        let hex = self.bytes[self.cursor] == b'x';
+
+        if hex {
+            self.cursor += 1;
+
+            if self.bytes.get(self.cursor) != Some(&b'"') {
+                return Err(self.unexpected());
+            }
+        }
+
+        self.cursor += 1;
+
+        let mut s = vec![];
+
+        loop {
+            let offset = self.cursor;
+
+            let b = *self
+                .bytes
+                .get(offset)
+                .ok_or_else(|| self.error(UnexpectedEndOfBuffer, offset))?;
+
+            self.cursor += 1;
+
+            match b {
+                b'"' => break,
+                b if hex && b.is_ascii_whitespace() => {},
+                b if hex && b.is_ascii_hexdigit() => s.push(b),
+                _ if hex => return Err(self.error(UnexpectedByte(b), offset)),
+                b'\\' => s.push(self.parse_escape()?),
+                b => s.push(b),
+            }
+        }
+
+        if hex {
+            return parse_hex(&s)
+                .map(BString::from)
+                .ok_or_else(|| self.error(InvalidData, self.cursor - 1));
+        }
+
+        Ok(BString::from(s))
+    }
+
+    /// Parses an escape sequence after the backslash.
+    fn parse_escape(&mut self) -> Result<u8, TextError> {
+        let offset = self.cursor;
+
+        let escape = *self
+            .bytes
+            .get(offset)
+            .ok_or_else(|| self.error(UnexpectedEndOfBuffer, offset))?;
+
+        self.cursor += 1;
+
+        match escape {
+            b'"' | b'\\' | b'\'' => Ok(escape),
+            b'n' => Ok(b'\n'),
+            b'r' => Ok(b'\r'),
+            b't' => Ok(b'\t'),
+            b'0' => Ok(0),
+            b'x' => {
+                let digits = self
+                    .bytes
+                    .get(self.cursor..self.cursor + 2)
+                    .and_then(parse_hex)
+                    .ok_or_else(|| self.error(InvalidData, offset - 1))?;
+
+                self.cursor += 2;
+
+                Ok(digits[0])
+            },
+            b => Err(self.error(UnexpectedByte(b), offset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bencode, Decoder, Settings};
+
+    #[test]
+    fn nested() {
+        let item = bencode!({ "b": [1, [], {}, ["x"]], "a": { "c": -7 }, "d": (Item::from(u64::MAX)) });
+
+        assert_eq!(
+            item.to_string(),
+            r#"{
+  "a": {
+    "c": -7
+  },
+  "b": [
+    1,
+    [],
+    {},
+    [
+      "x"
+    ]
+  ],
+  "d": 18446744073709551615
+}"#
+        );
+
+        let printer = Printer::new().setting(PrintSettings::Indent(0));
+
+        assert_eq!(printer.print(&bencode!([1, [2]])), "[\n1,\n[\n2\n]\n]");
+        assert_eq!(printer.print(&bencode!(5)), "5");
+    }
+
+    #[test]
+    fn strings() {
+        let item = bencode!(["quote \" backslash \\ tab \t nul \u{0} é", b"\x00\x7f\xff\"", { (&b"\xfe"[..]): "" }]);
+
+        let print = |style| Printer::new().setting(PrintSettings::Binary(style)).print(&item);
+
+        assert_eq!(
+            print(BinaryStyle::Hex),
+            "[\n  \"quote \\\" backslash \\\\ tab \\t nul \\x00 é\",\n  x\"007fff22\",\n  {\n    x\"fe\": \"\"\n  }\n]"
+        );
+        assert_eq!(
+            print(BinaryStyle::Escaped),
+            "[\n  \"quote \\\" backslash \\\\ tab \\t nul \\x00 é\",\n  \"\\x00\\x7f\\xff\\\"\",\n  {\n    \"\\xfe\": \"\"\n  }\n]"
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let item = bencode!({
+            "a": [&[0xffu8; 40], "é".repeat(3), "ab", { (&b"\xff\xff\xff"[..]): 1 }],
+            "pieces": &[0xffu8; 40],
+            "x": { "pieces": &[0xffu8; 41] },
+        });
+
+        let dump = Printer::new().setting(PrintSettings::MaxStringLength(2)).print(&item);
+
+        assert_eq!(
+            dump,
+            "{
+  \"a\": [
+    <40 bytes>,
+    <6 bytes>,
+    \"ab\",
+    {
+      x\"ffffff\": 1
+    }
+  ],
+  \"pieces\": <2 × 20 bytes>,
+  \"x\": {
+    \"pieces\": <41 bytes>
+  }
+}"
+        );
+    }
+
+    #[test]
+    fn elided_lists() {
+        let item = bencode!({ "a": [1, 2, 3], "b": [1] });
+
+        let print = |n| Printer::new().setting(PrintSettings::MaxListItems(n)).print(&item);
+
+        assert_eq!(print(2), "{\n  \"a\": [\n    1,\n    2,\n    <1 more item>\n  ],\n  \"b\": [\n    1\n  ]\n}");
+        assert_eq!(print(0), "{\n  \"a\": [\n    <3 items>\n  ],\n  \"b\": [\n    <1 item>\n  ]\n}");
+    }
+
+    #[test]
+    fn key_order() {
+        let item = Decoder::new(b"d1:bi1e1:ai2ee")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        assert_eq!(item.to_string(), "{\n  \"a\": 2,\n  \"b\": 1\n}");
+        assert_eq!(
+            Printer::new().setting(PrintSettings::KeepKeyOrder).print(&item),
+            "{\n  \"b\": 1,\n  \"a\": 2\n}"
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        let text = "
+            # comment \"
+            {
+                \"b\" : [ 1, -2 , 340282366920938463463374607431768211456, ],
+                x\"ff 00\": \"\\x41\\n\\t\\r\\0\\\\\\\"\\'é\",   # trailing comment
+                \"a\": {},
+                \"c\": [[]],
+            }
+        ";
+
+        let expected = bencode!({
+            "a": {},
+            "b": [1, -2, (Item::from(BBigInteger::new("340282366920938463463374607431768211456").unwrap()))],
+            "c": [[]],
+            (&b"\xff\x00"[..]): "A\n\t\r\0\\\"'é",
+        });
+
+        assert_eq!(from_text(text), Ok(expected));
+        assert_eq!(from_text("[]"), Ok(bencode!([])));
+        assert_eq!(from_text(" x\"\" "), Ok(bencode!(b"")));
+    }
+
+    #[test]
+    fn round_trip() {
+        let ordered = Decoder::new(b"d1:bi1e1:ai2ee")
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        let item = bencode!({
+            "name": "tab\t \"quoted\" \\ é \u{7f}",
+            "pieces": &[0x00u8, 0x7f, 0xff, b'"', b'\\', b'\''],
+            (&b"\xc3"[..]): [1, [], {}, (Item::from(i128::MIN))],
+            "ordered": ordered,
+        });
+
+        let expected = bencode!({
+            "name": "tab\t \"quoted\" \\ é \u{7f}",
+            "pieces": &[0x00u8, 0x7f, 0xff, b'"', b'\\', b'\''],
+            (&b"\xc3"[..]): [1, [], {}, (Item::from(i128::MIN))],
+            "ordered": { "a": 2, "b": 1 },
+        });
+
+        for style in [BinaryStyle::Hex, BinaryStyle::Escaped] {
+            let printer = Printer::new()
+                .setting(PrintSettings::Binary(style))
+                .setting(PrintSettings::MaxStringLength(usize::MAX));
+
+            assert_eq!(from_text(&printer.print(&item)), Ok(expected.clone()), "style = {:?}", style);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let cases: [(&str, ErrorKind, usize, usize); 12] = [
+            ("", UnexpectedEndOfBuffer, 1, 1),
+            ("[1 2]", UnexpectedByte(b'2'), 1, 4),
+            ("{\n  \"a\": 1,\n  \"a\": 2\n}", DuplicateKey, 3, 3),
+            ("{\n  \"é\" 1\n}", UnexpectedByte(b'1'), 2, 7),
+            ("[\n  <3 items>\n]", UnexpectedByte(b'<'), 2, 3),
+            ("[01]", LeadingZeros, 1, 2),
+            ("-0", NegativeZero, 1, 1),
+            ("\"abc", UnexpectedEndOfBuffer, 1, 5),
+            ("\"\\q\"", UnexpectedByte(b'q'), 1, 3),
+            ("\"\\x4\"", InvalidData, 1, 2),
+            ("x\"abc\"", InvalidData, 1, 6),
+            ("1 2", TrailingData, 1, 3),
+        ];
+
+        for (text, kind, line, column) in cases {
+            let e = from_text(text).unwrap_err();
+
+            assert_eq!((e.kind(), e.line(), e.column()), (kind, line, column), "text = {:?}", text);
+        }
+
+        assert_eq!(from_text("{1: 2}").unwrap_err().to_string(), "unexpected byte `49` at line 1, column 2");
+    }
+
+    #[test]
+    fn depth_limit() {
+        assert!(from_text(&format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH))).is_ok());
+
+        let e = from_text(&"[".repeat(100_000)).unwrap_err();
+
+        assert_eq!((e.kind(), e.column()), (DepthLimitExceeded, MAX_DEPTH + 1));
+    }
+}
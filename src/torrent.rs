@@ -0,0 +1,468 @@
+use crate::encode::Bencode;
+use crate::items::*;
+use crate::{Decoder, PathSegment, TorrentError};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Torrent metainfo, the contents of a `.torrent` file.
+///
+/// Keys that are not modelled here are kept in `extra` fields, so converting the metainfo
+/// back into an item keeps every key. The encoded bytes match the original ones only for
+/// canonical input, see [`Metainfo::from_item`].
+///
+/// ```
+/// use yabel::{Bencode, Files, Metainfo};
+///
+/// let bytes = b"d8:announce15:http://tracker/4:infod6:lengthi7e4:name3:foo\
+///               12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa6:sourcei1eee";
+///
+/// let metainfo = Metainfo::from_bytes(bytes).unwrap();
+///
+/// assert_eq!(metainfo.announce.as_deref(), Some("http://tracker/"));
+/// assert_eq!(metainfo.info.name, "foo");
+/// assert_eq!(metainfo.info.files, Files::Single { length: 7 });
+/// assert_eq!(metainfo.info.pieces, [[b'a'; 20]]);
+///
+/// // the unknown `source` key is kept
+/// assert_eq!(metainfo.encode(), bytes);
+/// ```
+pub struct Metainfo {
+    /// The URL of the tracker.
+    pub announce: Option<String>,
+    /// Tiers of tracker URLs, see BEP 12. Empty if there is no `announce-list`.
+    pub announce_list: Vec<Vec<String>>,
+    /// The creation time, in seconds since the Unix epoch.
+    pub creation_date: Option<i64>,
+    /// Free-form comment of the author.
+    pub comment: Option<String>,
+    /// Name and version of the program used to create the torrent.
+    pub created_by: Option<String>,
+    /// The encoding of the strings in `info`.
+    pub encoding: Option<String>,
+    /// The `info` dictionary.
+    pub info: Info,
+    /// Unknown keys of the top level dictionary.
+    pub extra: BDictionary<'static>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The `info` dictionary of torrent metainfo.
+pub struct Info {
+    /// The name of the file, or of the directory with the files.
+    pub name: String,
+    /// The number of bytes in each piece.
+    pub piece_length: u64,
+    /// The SHA-1 hashes of the pieces.
+    pub pieces: Vec<[u8; 20]>,
+    /// The file or the files of the torrent.
+    pub files: Files,
+    /// The `private` flag, see BEP 27.
+    pub private: Option<bool>,
+    /// Unknown keys of the `info` dictionary.
+    pub extra: BDictionary<'static>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The files of a torrent.
+pub enum Files {
+    /// A single file, named after [`Info::name`].
+    Single {
+        /// The length of the file in bytes.
+        length: u64,
+    },
+    /// A directory, named after [`Info::name`], with files.
+    Multiple(Vec<File>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A file of a multi-file torrent.
+pub struct File {
+    /// The length of the file in bytes.
+    pub length: u64,
+    /// The path of the file, one element per directory and the file name last.
+    pub path: Vec<String>,
+    /// Unknown keys of the file dictionary.
+    pub extra: BDictionary<'static>,
+}
+
+impl Metainfo {
+    /// Decodes metainfo with the default [`Decoder`] settings.
+    ///
+    /// To read torrents with unsorted dictionaries and other quirks, decode them with
+    /// suitable settings and use [`Metainfo::from_item`].
+    ///
+    /// # Errors
+    ///
+    /// See [`TorrentError`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TorrentError> {
+        Self::from_item(Decoder::new(bytes).decode_single()?)
+    }
+
+    /// Validates a decoded item and converts it into metainfo.
+    ///
+    /// [`BOrderedDictionary`] items are sorted, and integers and lengths are kept as values
+    /// rather than bytes. Encoding the metainfo, and so [`Info::hash_v1`] and
+    /// [`Info::hash_v2`], reproduce the original bytes only if the item was decoded from
+    /// canonical input, as it always is by [`Metainfo::from_bytes`]. Use
+    /// [`info_hash_v1`](crate::info_hash_v1) to hash the original bytes of other input.
+    ///
+    /// # Errors
+    ///
+    /// See [`TorrentError`].
+    pub fn from_item(item: Item<'_>) -> Result<Self, TorrentError> {
+        let mut fields = Fields::new(item.into_owned(), vec![])?;
+
+        let announce = fields.optional("announce", string)?;
+        let announce_list = fields.optional("announce-list", |i| {
+            i.list()?.0.into_iter().map(string_list).collect()
+        })?;
+        let creation_date = fields.optional("creation date", |i| i.as_int())?;
+        let comment = fields.optional("comment", string)?;
+        let created_by = fields.optional("created by", string)?;
+        let encoding = fields.optional("encoding", string)?;
+        let info = fields.required("info", Some)?;
+        let info = Info::from_fields(Fields::new(info, fields.path("info"))?)?;
+
+        Ok(Self {
+            announce,
+            announce_list: announce_list.unwrap_or_default(),
+            creation_date,
+            comment,
+            created_by,
+            encoding,
+            info,
+            extra: fields.dict,
+        })
+    }
+
+    /// Converts the metainfo into an item, including the unknown keys.
+    pub fn to_item(&self) -> Item<'static> {
+        let mut dict = self.extra.clone();
+
+        insert(&mut dict, "announce", self.announce.clone());
+
+        if !self.announce_list.is_empty() {
+            let tiers = self.announce_list.iter().map(|tier| strings(tier)).collect::<Vec<_>>();
+
+            insert(&mut dict, "announce-list", Some(tiers));
+        }
+
+        insert(&mut dict, "creation date", self.creation_date);
+        insert(&mut dict, "comment", self.comment.clone());
+        insert(&mut dict, "created by", self.created_by.clone());
+        insert(&mut dict, "encoding", self.encoding.clone());
+        insert(&mut dict, "info", Some(self.info.to_item()));
+
+        Item::Dictionary(dict)
+    }
+}
+
+impl Info {
+    fn from_fields(mut fields: Fields) -> Result<Self, TorrentError> {
+        let name = fields.required("name", string)?;
+        let piece_length = fields.required("piece length", |i| i.as_u64().filter(|l| *l > 0))?;
+        let pieces = fields.required("pieces", Item::string)?;
+
+        if pieces.0.len() % 20 != 0 {
+            return Err(TorrentError::InvalidPieces(pieces.0.len()));
+        }
+
+        let pieces = pieces
+            .0
+            .chunks_exact(20)
+            .map(|hash| hash.try_into().expect("the chunk is 20 bytes long"))
+            .collect();
+
+        let private = fields.optional("private", |i| match i.as_int() {
+            Some(0) => Some(false),
+            Some(1) => Some(true),
+            _ => None,
+        })?;
+
+        let files = match (fields.optional("length", |i| i.as_u64())?, fields.optional("files", Some)?) {
+            (Some(length), None) => Files::Single { length },
+            (None, Some(files)) => Files::Multiple(File::from_list(files, fields.path("files"))?),
+            _ => return Err(TorrentError::InvalidFileLayout),
+        };
+
+        Ok(Self {
+            name,
+            piece_length,
+            pieces,
+            files,
+            private,
+            extra: fields.dict,
+        })
+    }
+
+    /// Returns the total length of the files in bytes, or `None` if it does not fit into `u64`.
+    pub fn total_length(&self) -> Option<u64> {
+        match &self.files {
+            Files::Single { length } => Some(*length),
+            Files::Multiple(files) => files.iter().try_fold(0_u64, |total, f| total.checked_add(f.length)),
+        }
+    }
+
+    /// Converts the `info` dictionary into an item, including the unknown keys.
+    pub fn to_item(&self) -> Item<'static> {
+        let mut dict = self.extra.clone();
+
+        insert(&mut dict, "name", Some(self.name.clone()));
+        insert(&mut dict, "piece length", Some(self.piece_length));
+        insert(&mut dict, "pieces", Some(BString::from(self.pieces.concat())));
+        insert(&mut dict, "private", self.private.map(i64::from));
+
+        match &self.files {
+            Files::Single { length } => insert(&mut dict, "length", Some(*length)),
+            Files::Multiple(files) => {
+                let files = files.iter().map(File::to_item).collect::<Vec<_>>();
+
+                insert(&mut dict, "files", Some(files));
+            },
+        }
+
+        Item::Dictionary(dict)
+    }
+}
+
+impl File {
+    fn from_list(files: Item<'static>, path: Vec<PathSegment>) -> Result<Vec<Self>, TorrentError> {
+        let files = match files.list() {
+            Some(files) if !files.0.is_empty() => files,
+            _ => return Err(TorrentError::InvalidValue(path)),
+        };
+
+        files
+            .0
+            .into_iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let mut fields = Fields::new(file, [&path[..], &[PathSegment::Index(i)]].concat())?;
+
+                Ok(Self {
+                    length: fields.required("length", |i| i.as_u64())?,
+                    path: fields.required("path", |i| string_list(i).filter(|p| !p.is_empty()))?,
+                    extra: fields.dict,
+                })
+            })
+            .collect()
+    }
+
+    /// Converts the file dictionary into an item, including the unknown keys.
+    pub fn to_item(&self) -> Item<'static> {
+        let mut dict = self.extra.clone();
+
+        insert(&mut dict, "length", Some(self.length));
+        insert(&mut dict, "path", Some(strings(&self.path)));
+
+        Item::Dictionary(dict)
+    }
+}
+
+impl Bencode for &Metainfo {
+    fn encode(self) -> Vec<u8> {
+        self.to_item().encode()
+    }
+}
+
+impl Bencode for Metainfo {
+    fn encode(self) -> Vec<u8> {
+        (&self).encode()
+    }
+}
+
+/// A dictionary whose known keys are taken out one by one, leaving the unknown ones.
+struct Fields {
+    dict: BDictionary<'static>,
+    path: Vec<PathSegment>,
+}
+
+impl Fields {
+    fn new(item: Item<'static>, path: Vec<PathSegment>) -> Result<Self, TorrentError> {
+        let dict = match item {
+            Item::Dictionary(d) => d,
+            Item::OrderedDictionary(d) => d.into(),
+            _ if path.is_empty() => return Err(TorrentError::NotADictionary),
+            _ => return Err(TorrentError::InvalidValue(path)),
+        };
+
+        Ok(Self { dict, path })
+    }
+
+    /// Returns the path to the value of `key`.
+    fn path(&self, key: &str) -> Vec<PathSegment> {
+        [&self.path[..], &[PathSegment::from(key)]].concat()
+    }
+
+    /// Takes the value of `key` out, `convert` returns `None` for invalid values.
+    fn optional<T>(&mut self, key: &str, convert: impl FnOnce(Item<'static>) -> Option<T>) -> Result<Option<T>, TorrentError> {
+        match self.dict.0.remove(key.as_bytes()) {
+            Some(item) => convert(item)
+                .map(Some)
+                .ok_or_else(|| TorrentError::InvalidValue(self.path(key))),
+            None => Ok(None),
+        }
+    }
+
+    /// Takes the value of `key` out, failing if there is none.
+    fn required<T>(&mut self, key: &str, convert: impl FnOnce(Item<'static>) -> Option<T>) -> Result<T, TorrentError> {
+        self.optional(key, convert)?
+            .ok_or_else(|| TorrentError::MissingKey(self.path(key)))
+    }
+}
+
+/// Inserts the value, if there is one.
+fn insert<T: Into<Item<'static>>>(dict: &mut BDictionary<'static>, key: &'static str, value: Option<T>) {
+    if let Some(value) = value {
+        dict.0.insert(BString::from(key), value.into());
+    }
+}
+
+fn string(item: Item<'static>) -> Option<String> {
+    String::from_utf8(item.string()?.0.into_owned()).ok()
+}
+
+fn string_list(item: Item<'static>) -> Option<Vec<String>> {
+    item.list()?.0.into_iter().map(string).collect()
+}
+
+fn strings(strings: &[String]) -> Item<'static> {
+    strings.iter().cloned().map(Item::from).collect::<Vec<_>>().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bencode, Settings};
+
+    fn multi_file() -> Item<'static> {
+        bencode!({
+            "announce": "http://a/",
+            "announce-list": [["http://a/", "http://b/"], ["udp://c/"]],
+            "comment": "test",
+            "created by": "yabel",
+            "creation date": 1700000000,
+            "encoding": "UTF-8",
+            "url-list": ["http://seed/"],
+            "info": {
+                "name": "dir",
+                "piece length": 32768,
+                "pieces": &[7u8; 40],
+                "private": 1,
+                "files": [
+                    { "length": 3, "path": ["a", "b.txt"], "attr": "x" },
+                    { "length": 5, "path": ["c.txt"] },
+                ],
+                "meta version": 2,
+            },
+        })
+    }
+
+    #[test]
+    fn multiple_files() {
+        let item = multi_file();
+        let metainfo = Metainfo::from_item(item.clone()).unwrap();
+
+        assert_eq!(metainfo.announce_list, [vec!["http://a/", "http://b/"], vec!["udp://c/"]]);
+        assert_eq!(metainfo.creation_date, Some(1700000000));
+        assert_eq!(metainfo.comment.as_deref(), Some("test"));
+        assert_eq!(metainfo.created_by.as_deref(), Some("yabel"));
+        assert_eq!(metainfo.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(metainfo.extra.get_str("url-list[0]"), Some("http://seed/"));
+
+        let info = &metainfo.info;
+
+        assert_eq!((info.name.as_str(), info.piece_length, info.private), ("dir", 32768, Some(true)));
+        assert_eq!(info.pieces, [[7; 20], [7; 20]]);
+        assert_eq!(info.total_length(), Some(8));
+        assert_eq!(info.extra.get_int("meta version"), Some(2));
+
+        let mut overflowing = info.clone();
+
+        if let Files::Multiple(files) = &mut overflowing.files {
+            files[0].length = u64::MAX;
+        }
+
+        assert_eq!(overflowing.total_length(), None);
+
+        match &info.files {
+            Files::Multiple(files) => {
+                assert_eq!(files[0].path, ["a", "b.txt"]);
+                assert_eq!(files[0].extra.get_str("attr"), Some("x"));
+                assert_eq!(files[1].length, 5);
+            },
+            files => panic!("unexpected files: {:?}", files),
+        }
+
+        assert_eq!(metainfo.to_item(), item);
+        assert_eq!(metainfo.encode(), item.encode());
+    }
+
+    #[test]
+    fn unsorted_input() {
+        let bytes = b"d4:infod4:name1:a6:lengthi1e12:piece lengthi1e6:pieces0:ee";
+
+        assert!(Metainfo::from_bytes(bytes).is_err());
+
+        let item = Decoder::new(bytes)
+            .setting(Settings::UnsortedDictionaries)
+            .setting(Settings::PreserveDictionaryOrder)
+            .decode_single()
+            .unwrap();
+
+        let metainfo = Metainfo::from_item(item).unwrap();
+
+        assert_eq!(metainfo.info.files, Files::Single { length: 1 });
+        assert!(metainfo.info.pieces.is_empty());
+        assert_eq!(metainfo.encode(), b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee");
+    }
+
+    #[test]
+    fn errors() {
+        use TorrentError::*;
+
+        let path = |p: &str| p.split('.').map(PathSegment::from).collect::<Vec<_>>();
+
+        let with = |path: &str, value: Item<'static>| {
+            let mut item = multi_file();
+            *item.get_mut(path).unwrap() = value;
+            Metainfo::from_item(item).unwrap_err()
+        };
+
+        let without = |path: &str| {
+            let mut item = multi_file();
+            let (parent, key) = path.rsplit_once('.').unwrap_or(("", path));
+            item.get_mut(parent).unwrap().as_dict_mut().unwrap().0.remove(key.as_bytes());
+            Metainfo::from_item(item).unwrap_err()
+        };
+
+        assert_eq!(Metainfo::from_item(bencode!([])), Err(NotADictionary));
+        assert!(matches!(Metainfo::from_bytes(b"d"), Err(Decode(_))));
+
+        assert_eq!(without("info"), MissingKey(path("info")));
+        assert_eq!(without("info.name"), MissingKey(path("info.name")));
+        assert_eq!(without("info.pieces"), MissingKey(path("info.pieces")));
+        assert_eq!(without("info.files"), InvalidFileLayout);
+
+        assert_eq!(with("announce", bencode!(1)), InvalidValue(path("announce")));
+        assert_eq!(with("announce-list", bencode!([["a", 1]])), InvalidValue(path("announce-list")));
+        assert_eq!(with("comment", bencode!(b"\xff")), InvalidValue(path("comment")));
+        assert_eq!(with("info", bencode!("x")), InvalidValue(path("info")));
+        assert_eq!(with("info.piece length", bencode!(0)), InvalidValue(path("info.piece length")));
+        assert_eq!(with("info.pieces", bencode!(&[0u8; 21])), InvalidPieces(21));
+        assert_eq!(with("info.private", bencode!(2)), InvalidValue(path("info.private")));
+        assert_eq!(with("info.files", bencode!([])), InvalidValue(path("info.files")));
+
+        let e = with("info.files[1].length", bencode!(-1));
+
+        assert_eq!(e.to_string(), "invalid value of `info.files[1].length`");
+
+        let e = with("info.files[0].path", bencode!([]));
+
+        assert_eq!(e.to_string(), "invalid value of `info.files[0].path`");
+
+        let mut item = multi_file();
+        item.get_mut("info").unwrap().as_dict_mut().unwrap().0.insert("length".into(), 1.into());
+
+        assert_eq!(Metainfo::from_item(item), Err(InvalidFileLayout));
+    }
+}
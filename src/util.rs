@@ -0,0 +1,66 @@
+use std::fmt::{self, Write};
+
+/// The maximum nesting depth of lists and dictionaries, unless set otherwise.
+///
+/// Parsers recurse into nested items, so deeper input would overflow the stack.
+pub(crate) const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug)]
+/// How [`write_quoted`] escapes control characters.
+pub(crate) enum Escape {
+    /// `\b`, `\f` or `\u001f`, as JSON does.
+    Json,
+    /// `\x1f`, as the text notation does, `DEL` included.
+    Text,
+}
+
+/// Writes a quoted string, escaping quotes, backslashes and control characters.
+pub(crate) fn write_quoted<W: Write>(w: &mut W, s: &str, escape: Escape) -> fmt::Result {
+    w.write_char('"')?;
+
+    for c in s.chars() {
+        match (c, escape) {
+            ('"', _) => w.write_str("\\\"")?,
+            ('\\', _) => w.write_str("\\\\")?,
+            ('\n', _) => w.write_str("\\n")?,
+            ('\r', _) => w.write_str("\\r")?,
+            ('\t', _) => w.write_str("\\t")?,
+            ('\u{08}', Escape::Json) => w.write_str("\\b")?,
+            ('\u{0c}', Escape::Json) => w.write_str("\\f")?,
+            (c, Escape::Json) if c < ' ' => write!(w, "\\u{:04x}", c as u32)?,
+            (c, Escape::Text) if c.is_ascii_control() => write!(w, "\\x{:02x}", c as u32)?,
+            (c, _) => w.write_char(c)?,
+        }
+    }
+
+    w.write_char('"')
+}
+
+/// Writes the lowercase hex digits of `bytes`.
+pub(crate) fn write_hex<W: Write>(w: &mut W, bytes: &[u8]) -> fmt::Result {
+    for b in bytes {
+        write!(w, "{:02x}", b)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the lowercase hex digits of `bytes`.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+
+    write_hex(&mut s, bytes).expect("writing into a string does not fail");
+
+    s
+}
+
+/// Decodes hex digits of either case.
+pub(crate) fn parse_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}